@@ -0,0 +1,40 @@
+//! Compares the regular checker against the bitboard one.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use terminal_tombcrawler::{
+  bitboard::WallGrid,
+  generator::{generate, GeneratorConfig},
+  parse_to_level, Level,
+};
+
+const BRIGHTLEAF: &str = include_str!("../puzzles/01-brightleaf.ttc");
+
+fn bench_level(c: &mut Criterion, name: &str, level: &Level) {
+  let puzzle = level.puzzle();
+  let walls = level.solution().expect("level needs a solution");
+  let grid = WallGrid::from_solution(puzzle.width(), puzzle.height(), walls);
+
+  let mut group = c.benchmark_group(name);
+  group.bench_function("set-based", |b| {
+    b.iter(|| puzzle.check_solution(black_box(walls), false))
+  });
+  group.bench_function("bitboard", |b| {
+    b.iter(|| puzzle.wall_grid_ok(black_box(&grid)))
+  });
+  group.finish();
+}
+
+fn checker(c: &mut Criterion) {
+  let brightleaf = parse_to_level(BRIGHTLEAF).unwrap();
+  bench_level(c, "brightleaf", &brightleaf);
+
+  let mut config = GeneratorConfig::new(16, 16, 1);
+  config.require_unique = false;
+  let generated = generate(&config).expect("couldn't generate a 16x16");
+  bench_level(c, "generated 16x16", &generated);
+}
+
+criterion_group!(benches, checker);
+criterion_main!(benches);
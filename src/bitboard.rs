@@ -0,0 +1,353 @@
+//! Packed wall grids, one `u64` per row, and a checker that works on whole
+//! rows at a time.
+//!
+//! This is for hot loops like the solver and generator. Everything here
+//! gives the same answers as the regular checker; it's just faster at
+//! getting to "yes".
+
+use aglet::Coord;
+
+use crate::{checker::Failure, Puzzle, Solution, Tile};
+
+/// The widest board a [`WallGrid`] can hold.
+pub const MAX_WIDTH: u32 = 64;
+
+/// Walls packed into bits. Bit `x` of row `y` is set if `(x, y)` is a wall.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WallGrid {
+  width: u32,
+  height: u32,
+  rows: Vec<u64>,
+}
+
+impl WallGrid {
+  /// An all-open grid. Panics if `width` is over [`MAX_WIDTH`].
+  pub fn new(width: u32, height: u32) -> Self {
+    assert!(width <= MAX_WIDTH, "boards over 64 wide don't fit in a u64");
+    Self {
+      width,
+      height,
+      rows: vec![0; height as usize],
+    }
+  }
+
+  /// Copy the walls out of any other solution.
+  pub fn from_solution<S: Solution>(
+    width: u32,
+    height: u32,
+    solution: &S,
+  ) -> Self {
+    let mut grid = Self::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        if solution.is_wall(Coord::new(x, y)) {
+          grid.rows[y as usize] |= 1 << x;
+        }
+      }
+    }
+    grid
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn set(&mut self, coord: Coord, wall: bool) {
+    if coord.x >= self.width || coord.y >= self.height {
+      return;
+    }
+    let row = &mut self.rows[coord.y as usize];
+    if wall {
+      *row |= 1 << coord.x;
+    } else {
+      *row &= !(1 << coord.x);
+    }
+  }
+
+  pub fn toggle(&mut self, coord: Coord) {
+    if coord.x < self.width && coord.y < self.height {
+      self.rows[coord.y as usize] ^= 1 << coord.x;
+    }
+  }
+
+  /// The walls in row `y`, as bits.
+  pub fn row(&self, y: u32) -> u64 {
+    self.rows[y as usize]
+  }
+
+  pub fn row_wall_count(&self, y: u32) -> u32 {
+    self.rows[y as usize].count_ones()
+  }
+
+  pub fn column_wall_count(&self, x: u32) -> u32 {
+    self.rows.iter().filter(|&&row| row & (1 << x) != 0).count() as u32
+  }
+
+  /// Bits set for every column on the board.
+  fn mask(&self) -> u64 {
+    if self.width == 64 {
+      !0
+    } else {
+      (1 << self.width) - 1
+    }
+  }
+
+  /// Open cells in each row, as bits.
+  fn open_rows(&self) -> Vec<u64> {
+    let mask = self.mask();
+    self.rows.iter().map(|&row| !row & mask).collect()
+  }
+}
+
+impl Solution for WallGrid {
+  fn is_wall(&self, coord: Coord) -> bool {
+    coord.x < self.width
+      && coord.y < self.height
+      && self.rows[coord.y as usize] & (1 << coord.x) != 0
+  }
+}
+
+impl Puzzle {
+  /// The same as [`Puzzle::check_solution`], but much faster when the
+  /// solution is right.
+  ///
+  /// When something's wrong, this falls back to the regular checker to find
+  /// out exactly what.
+  pub fn check_wall_grid(&self, grid: &WallGrid) -> Result<(), Failure> {
+    if self.wall_grid_ok(grid) {
+      Ok(())
+    } else {
+      self.check_solution(grid, false)
+    }
+  }
+
+  /// Whether the grid solves the puzzle, without saying why not.
+  pub fn wall_grid_ok(&self, grid: &WallGrid) -> bool {
+    debug_assert_eq!(
+      (grid.width(), grid.height()),
+      (self.width(), self.height())
+    );
+    let height = self.height() as usize;
+    let mask = grid.mask();
+
+    // Hints, by popcount
+    for (y, &hint) in self.side_hints().iter().enumerate() {
+      if grid.rows[y].count_ones() != hint as u32 {
+        return false;
+      }
+    }
+    for (x, &hint) in self.top_hints().iter().enumerate() {
+      if grid.column_wall_count(x as u32) != hint as u32 {
+        return false;
+      }
+    }
+
+    let mut monsters = vec![0u64; height];
+    let mut chests = Vec::new();
+    for (coord, tile) in self.tiles.iter() {
+      match tile {
+        Tile::Monster => monsters[coord.y as usize] |= 1 << coord.x,
+        Tile::TreasureChest => chests.push(coord),
+      }
+    }
+
+    let open = grid.open_rows();
+    if open.iter().all(|&row| row == 0) {
+      return false;
+    }
+    // No walls on tiles
+    for (y, &row) in grid.rows.iter().enumerate() {
+      if row & monsters[y] != 0 {
+        return false;
+      }
+    }
+    if chests.iter().any(|&c| grid.is_wall(c)) {
+      return false;
+    }
+
+    // Dead ends, by counting neighbors in parallel. A cell is a dead end if
+    // fewer than two of its neighbors are open.
+    for y in 0..height {
+      let north = if y > 0 { open[y - 1] } else { 0 };
+      let south = if y + 1 < height { open[y + 1] } else { 0 };
+      let east = open[y] >> 1;
+      let west = (open[y] << 1) & mask;
+      let two_plus = (north & south)
+        | (north & east)
+        | (north & west)
+        | (south & east)
+        | (south & west)
+        | (east & west);
+      let dead_ends = open[y] & !two_plus;
+      if dead_ends != monsters[y] {
+        return false;
+      }
+    }
+
+    // Treasure rooms, which we only have to find once each
+    let mut claimed = vec![0u64; height];
+    for &chest in chests.iter() {
+      let room = (chest.y.saturating_sub(2)..=chest.y)
+        .flat_map(|cy| {
+          (chest.x.saturating_sub(2)..=chest.x)
+            .map(move |cx| Coord::new(cx, cy))
+        })
+        .filter(|c| c.x + 3 <= self.width() && c.y + 3 <= self.height())
+        .find(|&corner| self.check_room(grid, corner, chest).is_ok());
+      match room {
+        Some(corner) => {
+          for y in corner.y..corner.y + 3 {
+            claimed[y as usize] |= 0b111 << corner.x;
+          }
+        }
+        None => return false,
+      }
+    }
+
+    // 2x2 blocks, by shift-and-mask. Bit x of `blocks` is set if the block
+    // with top-left corner x is all open.
+    for y in 0..height.saturating_sub(1) {
+      let pairs = |row: u64| row & (row >> 1);
+      let blocks = pairs(open[y]) & pairs(open[y + 1]);
+      let allowed = pairs(claimed[y]) & pairs(claimed[y + 1]);
+      if blocks & !allowed != 0 {
+        return false;
+      }
+    }
+
+    // Connectivity, by flooding whole rows at a time
+    let start = open.iter().position(|&row| row != 0).unwrap();
+    let mut reached = vec![0u64; height];
+    reached[start] = open[start] & open[start].wrapping_neg();
+    loop {
+      let mut changed = false;
+      for y in 0..height {
+        let mut row = reached[y];
+        if y > 0 {
+          row |= reached[y - 1];
+        }
+        if y + 1 < height {
+          row |= reached[y + 1];
+        }
+        // Only open cells can be reached from above or below, or the
+        // sideways spread would step through walls diagonally
+        row &= open[y];
+        // Spread sideways as far as the row's open run goes
+        loop {
+          let spread = (row | (row << 1) | (row >> 1)) & open[y];
+          if spread == row {
+            break;
+          }
+          row = spread;
+        }
+        if row != reached[y] {
+          reached[y] = row;
+          changed = true;
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+    reached == open
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    parse_to_level,
+    testing::{board, random_board, Lcg, BRIGHTLEAF},
+  };
+
+  fn assert_agrees(puzzle: &Puzzle, walls: &impl Solution) -> bool {
+    let grid = WallGrid::from_solution(puzzle.width(), puzzle.height(), walls);
+    let expected = puzzle.check_solution(walls, false).is_ok();
+    assert_eq!(puzzle.wall_grid_ok(&grid), expected);
+    expected
+  }
+
+  #[test]
+  fn agrees_with_full_checker() {
+    let mut rng = Lcg(777);
+    let mut solved = 0;
+    for _ in 0..2000 {
+      let (width, height) = (2 + rng.below(7), 2 + rng.below(7));
+      let (puzzle, walls) = random_board(&mut rng, width, height);
+      if assert_agrees(&puzzle, &walls) {
+        solved += 1;
+      }
+    }
+    // Make sure both answers actually came up
+    assert!(0 < solved && solved < 2000, "{} solved", solved);
+  }
+
+  #[test]
+  fn walk_from_brightleaf() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let puzzle = level.puzzle();
+    let (width, height) = (puzzle.width(), puzzle.height());
+    let mut grid =
+      WallGrid::from_solution(width, height, level.solution().unwrap());
+    assert!(puzzle.wall_grid_ok(&grid));
+
+    let mut rng = Lcg(777);
+    for _ in 0..2000 {
+      let coord = Coord::new(rng.below(width), rng.below(height));
+      grid.toggle(coord);
+      assert_eq!(
+        puzzle.wall_grid_ok(&grid),
+        puzzle.check_solution(&grid, false).is_ok(),
+        "after toggling {}",
+        coord
+      );
+    }
+  }
+
+  #[test]
+  fn diagonals_dont_connect() {
+    let (puzzle, walls) = board(&["@#", "#@"]);
+    assert!(!assert_agrees(&puzzle, &walls));
+    let (puzzle, walls) = board(&["@.#", "#.#", "#.@"]);
+    assert!(assert_agrees(&puzzle, &walls));
+    let (puzzle, walls) = board(&["@.#", "##.", "#.@"]);
+    assert!(!assert_agrees(&puzzle, &walls));
+  }
+
+  #[test]
+  fn blocks_in_and_out_of_rooms() {
+    let room = ["#####", "#...#", "#.$.#", "#...#", "##@##"];
+    let (puzzle, walls) = board(&room);
+    assert!(assert_agrees(&puzzle, &walls));
+    let (puzzle, walls) = board(&["@..@", "#..#"]);
+    assert!(!assert_agrees(&puzzle, &walls));
+  }
+
+  #[test]
+  fn failed_rooms() {
+    // Two ways in
+    let (puzzle, walls) = board(&["#####", "#...@", "#.$.#", "#...#", "##@##"]);
+    assert!(!assert_agrees(&puzzle, &walls));
+    // A monster inside
+    let (puzzle, walls) = board(&["#####", "#..@#", "#.$.#", "#...#", "##@##"]);
+    assert!(!assert_agrees(&puzzle, &walls));
+  }
+
+  #[test]
+  fn counts() {
+    let mut grid = WallGrid::new(5, 3);
+    grid.set(Coord::new(0, 0), true);
+    grid.set(Coord::new(4, 0), true);
+    grid.toggle(Coord::new(4, 2));
+    assert_eq!(grid.row_wall_count(0), 2);
+    assert_eq!(grid.column_wall_count(4), 2);
+    grid.set(Coord::new(4, 0), false);
+    assert_eq!(grid.row_wall_count(0), 1);
+    assert!(!grid.is_wall(Coord::new(5, 0)));
+  }
+}
@@ -0,0 +1,153 @@
+//! A board the player (or anything else) fills in over time.
+
+use aglet::Coord;
+
+use crate::{Puzzle, Solution};
+
+/// What's known about one cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cell {
+  Unknown,
+  Wall,
+  Open,
+}
+
+/// A puzzle and a partial answer to it.
+///
+/// Unknown cells count as open as far as [`Solution`] is concerned.
+#[derive(Debug, Clone)]
+pub struct Board {
+  puzzle: Puzzle,
+  cells: Vec<Cell>,
+  row_walls: Vec<u32>,
+  column_walls: Vec<u32>,
+}
+
+impl Board {
+  /// A board with nothing filled in.
+  pub fn new(puzzle: Puzzle) -> Self {
+    let (width, height) = (puzzle.width(), puzzle.height());
+    Self {
+      puzzle,
+      cells: vec![Cell::Unknown; (width * height) as usize],
+      row_walls: vec![0; height as usize],
+      column_walls: vec![0; width as usize],
+    }
+  }
+
+  pub fn puzzle(&self) -> &Puzzle {
+    &self.puzzle
+  }
+
+  pub fn width(&self) -> u32 {
+    self.puzzle.width()
+  }
+
+  pub fn height(&self) -> u32 {
+    self.puzzle.height()
+  }
+
+  /// Out of bounds cells are `Unknown`.
+  pub fn get(&self, coord: Coord) -> Cell {
+    match self.index(coord) {
+      Some(idx) => self.cells[idx],
+      None => Cell::Unknown,
+    }
+  }
+
+  /// Returns what was there before. Does nothing out of bounds.
+  pub fn set(&mut self, coord: Coord, cell: Cell) -> Cell {
+    let idx = match self.index(coord) {
+      Some(it) => it,
+      None => return Cell::Unknown,
+    };
+    let old = std::mem::replace(&mut self.cells[idx], cell);
+    let (x, y) = (coord.x as usize, coord.y as usize);
+    if old == Cell::Wall {
+      self.row_walls[y] -= 1;
+      self.column_walls[x] -= 1;
+    }
+    if cell == Cell::Wall {
+      self.row_walls[y] += 1;
+      self.column_walls[x] += 1;
+    }
+    old
+  }
+
+  /// Set the cell to `cell`, or back to unknown if it already was.
+  pub fn toggle(&mut self, coord: Coord, cell: Cell) -> Cell {
+    let new = if self.get(coord) == cell {
+      Cell::Unknown
+    } else {
+      cell
+    };
+    self.set(coord, new)
+  }
+
+  pub fn clear(&mut self, coord: Coord) -> Cell {
+    self.set(coord, Cell::Unknown)
+  }
+
+  /// Make every cell unknown again.
+  pub fn clear_all(&mut self) {
+    self.cells.fill(Cell::Unknown);
+    self.row_walls.fill(0);
+    self.column_walls.fill(0);
+  }
+
+  pub fn row_wall_count(&self, y: u32) -> u32 {
+    self.row_walls[y as usize]
+  }
+
+  pub fn column_wall_count(&self, x: u32) -> u32 {
+    self.column_walls[x as usize]
+  }
+
+  /// Every coordinate on the board, in row-major order.
+  pub fn coords(&self) -> impl Iterator<Item = Coord> {
+    let width = self.width();
+    (0..self.height())
+      .flat_map(move |y| (0..width).map(move |x| Coord::new(x, y)))
+  }
+
+  fn index(&self, coord: Coord) -> Option<usize> {
+    (coord.x < self.width() && coord.y < self.height())
+      .then(|| (coord.y * self.width() + coord.x) as usize)
+  }
+}
+
+impl Solution for Board {
+  fn is_wall(&self, coord: Coord) -> bool {
+    self.get(coord) == Cell::Wall
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use aglet::Grid;
+
+  use super::*;
+
+  #[test]
+  fn counts_follow_edits() {
+    let puzzle = Puzzle::new(Grid::new(3, 2), vec![0; 3], vec![0; 2]);
+    let mut board = Board::new(puzzle);
+    let a = Coord::new(1, 0);
+    let b = Coord::new(1, 1);
+
+    board.set(a, Cell::Wall);
+    board.toggle(b, Cell::Wall);
+    assert_eq!(board.column_wall_count(1), 2);
+    assert_eq!(board.row_wall_count(0), 1);
+
+    // Toggling to something else replaces, toggling again clears
+    assert_eq!(board.toggle(a, Cell::Open), Cell::Wall);
+    assert_eq!(board.toggle(a, Cell::Open), Cell::Open);
+    assert_eq!(board.get(a), Cell::Unknown);
+    assert_eq!(board.column_wall_count(1), 1);
+
+    board.clear(b);
+    assert_eq!(board.row_wall_count(1), 0);
+    assert!(!board.is_wall(Coord::new(5, 5)));
+  }
+}
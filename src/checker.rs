@@ -1,4 +1,6 @@
-use aglet::{Coord, CoordVec, Direction8};
+use std::fmt;
+
+use aglet::{Coord, CoordVec};
 use ahash::AHashSet;
 
 use crate::{Puzzle, Solution, Tile};
@@ -19,136 +21,218 @@ macro_rules! dbgprn {
 
 impl Puzzle {
   /// - All dead ends contain a monster; all monsters are in a dead end.
-  /// - Each treasure chest is in a 3x3 area with exactly one entrance.
+  /// - Each treasure chest is in a 3x3 area with exactly one entrance, and
+  ///   nothing else in it.
   /// - Besides treasure rooms, there are no 2x2 corridors.
   /// - All corridors are connected.
+  /// - Each row and column has as many walls as its hint says.
+  ///
+  /// Stops at the first problem found. That's the problem whose reason has
+  /// the best [`FailureReason::priority`], and among those, the first one in
+  /// row-major order, so the same board always gets the same answer.
   pub fn check_solution<S: Solution>(
     &self,
     solution: &S,
     debug: bool,
   ) -> Result<(), Failure> {
-    let (chests, big_opens) = self.check_shape(solution, debug)?;
+    let mut failures = Failures::first_only();
+    let _ = self.check_into(solution, &mut failures, debug);
+    match failures.list.pop() {
+      Some(fail) => Err(fail),
+      None => Ok(()),
+    }
+  }
+
+  /// Like [`Puzzle::check_solution`], but finds every problem instead of
+  /// stopping at the first.
+  ///
+  /// The failures are sorted by position, row-major, and then by
+  /// [`FailureReason::priority`].
+  pub fn check_solution_all<S: Solution>(
+    &self,
+    solution: &S,
+    debug: bool,
+  ) -> Vec<Failure> {
+    let mut failures = Failures::all();
+    let _ = self.check_into(solution, &mut failures, debug);
+    let mut list = failures.list;
+    list.sort_by_key(|f| (f.pos.y, f.pos.x, f.reason.priority()));
+    list
+  }
+
+  fn check_into<S: Solution>(
+    &self,
+    solution: &S,
+    failures: &mut Failures,
+    debug: bool,
+  ) -> Result<(), Stop> {
+    let (chests, big_blocks) = self.check_shape(solution, failures, debug)?;
 
     let mut claimed_by_chests = AHashSet::new();
     for chest in chests {
-      let ext = self.check_chest(solution, chest, debug)?;
-      claimed_by_chests.extend(ext);
+      match self.check_chest(solution, chest, debug) {
+        Ok(owned) => claimed_by_chests.extend(owned),
+        Err(fail) => failures.push(fail)?,
+      }
     }
 
-    let unclaimed = big_opens.difference(&claimed_by_chests);
-    // for now
-    let unclaimed = unclaimed.collect::<Vec<_>>();
-    if !unclaimed.is_empty() {
-      dbgprn!(debug, "these were not owned: {:?}", &unclaimed);
-      return Err(Failure::new(
-        *unclaimed[0],
-        FailureReason::LargeAreaOutsideOfTreasureRoom,
-      ));
+    for block in big_blocks {
+      if !block.iter().all(|c| claimed_by_chests.contains(c)) {
+        dbgprn!(debug, "the 2x2 at {} is not owned", block[0]);
+        failures.push(Failure::new_region(
+          FailureReason::LargeAreaOutsideOfTreasureRoom(block),
+          block.to_vec(),
+        ))?;
+      }
     }
 
+    self.check_hints(solution, failures, debug)?;
+
     Ok(())
   }
+
+  /// Check that the wall count of every row and column matches its hint.
+  fn check_hints<S: Solution>(
+    &self,
+    solution: &S,
+    failures: &mut Failures,
+    debug: bool,
+  ) -> Result<(), Stop> {
+    for (y, &hint) in self.side_hints().iter().enumerate() {
+      let y = y as u32;
+      let count = (0..self.width())
+        .filter(|&x| solution.is_wall(Coord::new(x, y)))
+        .count();
+      if count != hint as usize {
+        dbgprn!(debug, "row {} has {} walls, wanted {}", y, count, hint);
+        failures.push(Failure::new_region(
+          FailureReason::RowWallCountMismatch(y),
+          (0..self.width()).map(|x| Coord::new(x, y)).collect(),
+        ))?;
+      }
+    }
+    for (x, &hint) in self.top_hints().iter().enumerate() {
+      let x = x as u32;
+      let count = (0..self.height())
+        .filter(|&y| solution.is_wall(Coord::new(x, y)))
+        .count();
+      if count != hint as usize {
+        dbgprn!(debug, "column {} has {} walls, wanted {}", x, count, hint);
+        failures.push(Failure::new_region(
+          FailureReason::ColumnWallCountMismatch(x),
+          (0..self.height()).map(|y| Coord::new(x, y)).collect(),
+        ))?;
+      }
+    }
+
+    Ok(())
+  }
+
   /// Check that:
   /// - No overlaps
   /// - Everything is contiguous
   /// - Dead end <=> monster
   ///
-  /// Also return chest locations
+  /// Also return chest locations and the top-left corners of all 2x2 open
+  /// blocks, in row-major order
   fn check_shape<S: Solution>(
     &self,
     solution: &S,
+    failures: &mut Failures,
     debug: bool,
-  ) -> Result<(AHashSet<Coord>, AHashSet<Coord>), Failure> {
-    let (openings, monsters, chests) = {
+  ) -> Result<(Vec<Coord>, Vec<[Coord; 4]>), Stop> {
+    // Sets are for lookup only; anything that can produce a failure walks
+    // `open_order` so the result doesn't depend on hash order.
+    let (openings, open_order, monsters, chests, overlaps) = {
+      let mut overlaps = Vec::new();
       let mut openings = AHashSet::new();
+      let mut open_order = Vec::new();
       let mut monsters = AHashSet::new();
-      let mut chests = AHashSet::new();
+      let mut chests = Vec::new();
 
       for y in 0..self.height() {
         for x in 0..self.width() {
           let coord = Coord::new(x, y);
-          if let Some(tile) = self.get_tile(coord) {
-            let slot = match tile {
-              Tile::Monster => &mut monsters,
-              Tile::TreasureChest => &mut chests,
-            };
-            slot.insert(coord);
+          match self.get_tile(coord) {
+            Some(Tile::Monster) => {
+              monsters.insert(coord);
+            }
+            Some(Tile::TreasureChest) => chests.push(coord),
+            None => {}
           }
 
           if solution.is_wall(coord) {
             if let Some(tile) = self.get_tile(coord) {
-              return Err(Failure {
-                reason: FailureReason::WallOverlapsFilledTile(tile),
-                pos: coord,
-              });
+              overlaps.push(Failure::new(
+                coord,
+                FailureReason::WallOverlapsFilledTile(tile),
+              ));
             }
           } else {
             openings.insert(coord);
+            open_order.push(coord);
           }
         }
       }
-      (openings, monsters, chests)
+      (openings, open_order, monsters, chests, overlaps)
     };
 
-    let reachable_via_floodfill = {
-      let mut rvf = AHashSet::new();
-      let start = match openings.iter().next() {
-        Some(it) => it,
-        None => {
-          // If we're here, then we know there's no walls overlapping stuff.
-          // So that means there's no puzzle components and
-          // it's technically correct to fill totally.
-          return Ok((chests, AHashSet::new()));
-        }
+    let areas = components(&open_order);
+    if areas.is_empty() {
+      // A board of solid rock has no dead ends to check, but it's not much of
+      // a dungeon either.
+      let everything = (0..self.height())
+        .flat_map(|y| (0..self.width()).map(move |x| Coord::new(x, y)))
+        .collect::<Vec<_>>();
+      let failure = if everything.is_empty() {
+        Failure::new(Coord::new(0, 0), FailureReason::EntirelyFilledWithWalls)
+      } else {
+        Failure::new_region(FailureReason::EntirelyFilledWithWalls, everything)
       };
-      let mut todo = vec![*start];
-      while let Some(here) = todo.pop() {
-        if rvf.insert(here) {
-          for n in here.neighbors4() {
-            if openings.contains(&n) {
-              todo.push(n);
-            }
-          }
-        }
-      }
-      rvf
-    };
-
-    let mut big_opens = AHashSet::new();
-    for coord in openings.iter().copied() {
-      if !reachable_via_floodfill.contains(&coord) {
-        return Err(Failure::new(coord, FailureReason::DiscontiguousAreas));
-      }
+      failures.push(failure)?;
+    }
+    // Checks go in priority order, so stopping at the first failure gets the
+    // one `check_solution` promises. Overlaps wait for the all-walls check.
+    for overlap in overlaps {
+      failures.push(overlap)?;
+    }
+    if areas.is_empty() {
+      return Ok((chests, Vec::new()));
+    }
+    // The biggest area is the "real" one; the rest are the stragglers.
+    // On ties, the earliest wins.
+    let main = areas
+      .iter()
+      .enumerate()
+      .rev()
+      .max_by_key(|(_, area)| area.len())
+      .map(|(i, _)| i)
+      .unwrap();
+    if areas.len() > 1 {
+      let stragglers = areas
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != main)
+        .flat_map(|(_, area)| area.iter().copied())
+        .collect();
+      failures.push(Failure::new_region(
+        FailureReason::DiscontiguousAreas(areas),
+        stragglers,
+      ))?;
+    }
 
-      // To check for 2x2s we see if 3 consecutive neighbors,
-      // two orthag and one diag, are empty
-      'runs: for orthag in [
-        Direction8::North,
-        Direction8::East,
-        Direction8::South,
-        Direction8::West,
-      ] {
-        let neighbor_dirs = [orthag, orthag.rotate_by(1), orthag.rotate_by(2)];
-        let too_big = neighbor_dirs.iter().all(|&nd| {
-          if let Some(neighbor) = (coord.to_icoord() + nd.deltas()).to_coord() {
-            let wall = neighbor.x >= self.width()
-              || neighbor.y >= self.height()
-              || solution.is_wall(neighbor);
-            !wall
-          } else {
-            false
-          }
-        });
-        if too_big {
-          dbgprn!(
-            debug,
-            "{} marked as too big with run {:?}",
-            coord,
-            &neighbor_dirs,
-          );
-          big_opens.insert(coord);
-          break 'runs;
-        }
+    let mut big_blocks = Vec::new();
+    for coord in open_order.iter().copied() {
+      // To check for 2x2s we look at each cell as the top-left of a block
+      let block = [
+        coord,
+        Coord::new(coord.x + 1, coord.y),
+        Coord::new(coord.x, coord.y + 1),
+        Coord::new(coord.x + 1, coord.y + 1),
+      ];
+      if block.iter().all(|c| openings.contains(c)) {
+        dbgprn!(debug, "{} is the corner of a 2x2", coord);
+        big_blocks.push(block);
       }
       // Dead ends have 3 wall cells.
       let neighbor_count = coord
@@ -161,159 +245,649 @@ impl Puzzle {
         })
         .count();
       match neighbor_count {
-        0 | 1 | 2 => {
+        0..=2 => {
           if monsters.contains(&coord) {
-            return Err(Failure::new(
+            failures.push(Failure::new(
               coord,
               FailureReason::MonsterWithoutDeadEnd,
-            ));
+            ))?;
           }
         }
         3 | 4 => {
           if !monsters.contains(&coord) {
-            return Err(Failure::new(
+            failures.push(Failure::new(
               coord,
               FailureReason::DeadEndWithoutMonster,
-            ));
+            ))?;
           }
         }
         _ => unreachable!(),
       }
     }
 
-    Ok((chests, big_opens))
+    Ok((chests, big_blocks))
   }
 
-  fn check_chest<S: Solution>(
+  /// Find the chest's treasure room, following the original game:
+  /// - The room is a 3x3 square of open cells lying entirely on the board,
+  ///   with the chest somewhere inside.
+  /// - It holds no monsters and no other chests.
+  /// - Exactly one of the 12 cells touching its sides is open. The board edge
+  ///   counts as wall, and the diagonal corners don't count at all.
+  ///
+  /// Returns the cells of the room.
+  pub(crate) fn check_chest<S: Solution>(
     &self,
     solution: &S,
     chest: Coord,
     debug: bool,
-  ) -> Result<impl IntoIterator<Item = Coord>, Failure> {
-    // interestinly the source code doesn't actually appear to check
-    // for one entrance?
+  ) -> Result<Vec<Coord>, Failure> {
     dbgprn!(debug, "checking chest at {}", chest);
-    let min_corner_x = chest.x.saturating_sub(2);
-    let max_corner_x = (min_corner_x + 2).min(self.width());
-    let min_corner_y = chest.y.saturating_sub(2);
-    let max_corner_y = (min_corner_y + 2).min(self.height());
-    dbgprn!(
-      debug,
-      "scanning x in {}..={}, y in {}..={}",
-      min_corner_x,
-      max_corner_x,
-      min_corner_y,
-      max_corner_y
-    );
-    for corner_y in min_corner_y..=max_corner_y {
-      'pick_corner: for corner_x in min_corner_x..=max_corner_x {
+    let mut attempts = Vec::new();
+    for corner_y in chest.y.saturating_sub(2)..=chest.y {
+      for corner_x in chest.x.saturating_sub(2)..=chest.x {
         dbgprn!(debug, "  trying the corner to be {},{}", corner_x, corner_y);
-        let mut owned = Vec::new();
-
-        for y in corner_y..corner_y + 3 {
-          for x in corner_x..corner_x + 3 {
-            let here = Coord::new(x, y);
-            if solution.is_wall(here) {
-              // this corner is invalid womp womp
-              // the src code checks for non-monster also, but they'd be ruled
-              // out by the no-2x2 rule.
-              dbgprn!(
-                debug,
-                "    there was a wall at {}, trying new corner",
-                here
-              );
-              continue 'pick_corner;
-            }
-            owned.push(here);
+        let corner = Coord::new(corner_x, corner_y);
+        if corner_x + 3 > self.width() || corner_y + 3 > self.height() {
+          dbgprn!(debug, "    spills off the board");
+          attempts.push(RoomAttempt::new(corner, RoomRejection::OutOfBounds));
+          continue;
+        }
+
+        match self.check_room(solution, corner, chest) {
+          Ok(owned) => {
+            dbgprn!(debug, "succeeded at {}! owns {:?}", corner, &owned);
+            return Ok(owned);
+          }
+          Err(rejection) => {
+            dbgprn!(debug, "    rejected: {:?}", &rejection);
+            attempts.push(RoomAttempt::new(corner, rejection));
           }
         }
+      }
+    }
 
-        // given this corner position, search the border.
-        // don't search the corners, though.
-        dbgprn!(
-          debug,
-          "  succeeded at no wall check, checking for exactly one entrance"
-        );
-        let top_bottom =
-          (corner_x as i32..=corner_x as i32 + 2).flat_map(|x| {
-            [corner_y as i32 - 1, corner_y as i32 + 3]
-              .into_iter()
-              .map(move |y| CoordVec::new(x, y))
-          });
-        let left_right =
-          (corner_y as i32..=corner_y as i32 + 2).flat_map(|y| {
-            [corner_x as i32 - 1, corner_x as i32 + 3]
-              .into_iter()
-              .map(move |x| CoordVec::new(x, y))
-          });
-
-        let mut found_empty = false;
-        for border_coord in top_bottom.chain(left_right) {
-          let is_wall = match border_coord.to_coord() {
-            None => true,
-            Some(it) => solution.is_wall(it),
-          };
-          dbgprn!(
-            debug,
-            "    checking border pos {} (wall={})",
-            border_coord,
-            is_wall
-          );
-          if !is_wall {
-            match found_empty {
-              false => {
-                dbgprn!(debug, "      haven't found an empty yet");
-                found_empty = true;
-              }
-              true => {
-                // this is not the spot :(
-                dbgprn!(
-                  debug,
-                  "      have found an empty yet, trying new corner"
-                );
-                continue 'pick_corner;
-              }
-            }
+    Err(Failure::new(chest, FailureReason::NoTreasureRoom(attempts)))
+  }
+
+  /// Check one in-bounds 3x3 room with the given top-left corner.
+  pub(crate) fn check_room<S: Solution>(
+    &self,
+    solution: &S,
+    corner: Coord,
+    chest: Coord,
+  ) -> Result<Vec<Coord>, RoomRejection> {
+    let mut owned = Vec::new();
+    for y in corner.y..corner.y + 3 {
+      for x in corner.x..corner.x + 3 {
+        let here = Coord::new(x, y);
+        if solution.is_wall(here) {
+          return Err(RoomRejection::WallInside(here));
+        }
+        match self.get_tile(here) {
+          Some(Tile::Monster) => {
+            return Err(RoomRejection::MonsterInside(here))
+          }
+          Some(Tile::TreasureChest) if here != chest => {
+            return Err(RoomRejection::ExtraChest(here))
           }
+          _ => {}
         }
+        owned.push(here);
+      }
+    }
 
-        if found_empty {
-          // yayayyayay!
-          dbgprn!(
-            debug,
-            "succeeded at {},{}! owns {:?}",
-            corner_x,
-            corner_y,
-            &owned
-          );
-          return Ok(owned);
+    let entrances = room_border(corner.x, corner.y)
+      .into_iter()
+      .filter_map(|(x, y)| {
+        let here = CoordVec::new(x, y).to_coord()?;
+        let on_board = here.x < self.width() && here.y < self.height();
+        (on_board && !solution.is_wall(here)).then_some(here)
+      })
+      .collect::<Vec<_>>();
+    match entrances.len() {
+      0 => Err(RoomRejection::NoEntrance),
+      1 => Ok(owned),
+      _ => Err(RoomRejection::TooManyEntrances(entrances)),
+    }
+  }
+}
+
+/// The 12 cells touching the sides of a 3x3 room, not counting diagonals.
+pub(crate) fn room_border(cx: u32, cy: u32) -> [(i32, i32); 12] {
+  let (cx, cy) = (cx as i32, cy as i32);
+  let mut out = [(0, 0); 12];
+  for i in 0..3 {
+    out[i * 4] = (cx + i as i32, cy - 1);
+    out[i * 4 + 1] = (cx + i as i32, cy + 3);
+    out[i * 4 + 2] = (cx - 1, cy + i as i32);
+    out[i * 4 + 3] = (cx + 3, cy + i as i32);
+  }
+  out
+}
+
+/// Split some cells into 4-connected components.
+///
+/// Components come out in the order of their first cell in `cells`.
+fn components(cells: &[Coord]) -> Vec<Vec<Coord>> {
+  let lookup = cells.iter().copied().collect::<AHashSet<_>>();
+  let mut seen = AHashSet::new();
+  let mut out = Vec::new();
+  for &start in cells.iter() {
+    if seen.contains(&start) {
+      continue;
+    }
+    let mut area = Vec::new();
+    let mut todo = vec![start];
+    while let Some(here) = todo.pop() {
+      if seen.insert(here) {
+        area.push(here);
+        for n in here.neighbors4() {
+          if lookup.contains(&n) {
+            todo.push(n);
+          }
         }
       }
     }
+    out.push(area);
+  }
+  out
+}
+
+/// Where failures get collected while checking.
+struct Failures {
+  list: Vec<Failure>,
+  all: bool,
+}
+
+/// Returned through `?` when we've found all the failures we want.
+struct Stop;
+
+impl Failures {
+  fn first_only() -> Self {
+    Self {
+      list: Vec::new(),
+      all: false,
+    }
+  }
 
-    Err(Failure::new(chest, FailureReason::NoTreasureRoom))
+  fn all() -> Self {
+    Self {
+      list: Vec::new(),
+      all: true,
+    }
+  }
+
+  fn push(&mut self, failure: Failure) -> Result<(), Stop> {
+    self.list.push(failure);
+    if self.all {
+      Ok(())
+    } else {
+      Err(Stop)
+    }
   }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Failure {
   pub reason: FailureReason,
+  /// The top-left-most cell of `region`.
   pub pos: Coord,
+  /// Every cell involved in the problem.
+  pub region: Vec<Coord>,
 }
 
 impl Failure {
   pub fn new(pos: Coord, reason: FailureReason) -> Self {
-    Self { reason, pos }
+    Self {
+      reason,
+      pos,
+      region: vec![pos],
+    }
+  }
+
+  /// Make a failure covering many cells. `region` must not be empty.
+  pub fn new_region(reason: FailureReason, mut region: Vec<Coord>) -> Self {
+    region.sort_by_key(|c| (c.y, c.x));
+    Self {
+      reason,
+      pos: region[0],
+      region,
+    }
   }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FailureReason {
   EntirelyFilledWithWalls,
   WallOverlapsFilledTile(Tile),
-  DiscontiguousAreas,
+  /// Every 4-connected area of open cells, including the biggest one.
+  DiscontiguousAreas(Vec<Vec<Coord>>),
   DeadEndWithoutMonster,
   MonsterWithoutDeadEnd,
-  NoTreasureRoom,
-  LargeAreaOutsideOfTreasureRoom,
+  /// Every 3x3 room we tried for the chest, and why it didn't work.
+  NoTreasureRoom(Vec<RoomAttempt>),
+  /// The open 2x2 block, top-left corner first.
+  LargeAreaOutsideOfTreasureRoom([Coord; 4]),
+  /// The given row has the wrong number of walls for its hint. Rows are
+  /// zero-indexed here, but get printed one-indexed.
+  RowWallCountMismatch(u32),
+  /// The given column has the wrong number of walls for its hint, and is
+  /// printed one-indexed too.
+  ColumnWallCountMismatch(u32),
+}
+
+impl FailureReason {
+  /// Which failures get reported first by [`Puzzle::check_solution`];
+  /// lower goes first.
+  ///
+  /// Broken tiles and connectivity come before local shape problems, and
+  /// hint counts come last, since they're already visible on the hints.
+  pub fn priority(&self) -> u8 {
+    match self {
+      FailureReason::EntirelyFilledWithWalls => 0,
+      FailureReason::WallOverlapsFilledTile(_) => 1,
+      FailureReason::DiscontiguousAreas(_) => 2,
+      FailureReason::DeadEndWithoutMonster
+      | FailureReason::MonsterWithoutDeadEnd => 3,
+      FailureReason::NoTreasureRoom(_) => 4,
+      FailureReason::LargeAreaOutsideOfTreasureRoom(_) => 5,
+      FailureReason::RowWallCountMismatch(_) => 6,
+      FailureReason::ColumnWallCountMismatch(_) => 7,
+    }
+  }
+}
+
+impl fmt::Display for FailureReason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FailureReason::EntirelyFilledWithWalls => {
+        write!(f, "the whole board is walls")
+      }
+      FailureReason::WallOverlapsFilledTile(tile) => {
+        write!(f, "a wall is on top of a {:?}", tile)
+      }
+      FailureReason::DiscontiguousAreas(areas) => {
+        write!(f, "the corridors are split into {} areas", areas.len())
+      }
+      FailureReason::DeadEndWithoutMonster => {
+        write!(f, "a dead end has no monster")
+      }
+      FailureReason::MonsterWithoutDeadEnd => {
+        write!(f, "a monster isn't in a dead end")
+      }
+      FailureReason::NoTreasureRoom(attempts) => {
+        write!(f, "a chest has no treasure room ({} tried)", attempts.len())
+      }
+      FailureReason::LargeAreaOutsideOfTreasureRoom(_) => {
+        write!(f, "a 2x2 area isn't in a treasure room")
+      }
+      FailureReason::RowWallCountMismatch(y) => {
+        write!(f, "row {} has the wrong number of walls", y + 1)
+      }
+      FailureReason::ColumnWallCountMismatch(x) => {
+        write!(f, "column {} has the wrong number of walls", x + 1)
+      }
+    }
+  }
+}
+
+/// A 3x3 room we tried to fit around a chest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomAttempt {
+  /// Top-left corner of the room.
+  pub corner: Coord,
+  pub rejection: RoomRejection,
+}
+
+impl RoomAttempt {
+  pub fn new(corner: Coord, rejection: RoomRejection) -> Self {
+    Self { corner, rejection }
+  }
+}
+
+/// Why a room didn't work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoomRejection {
+  /// Part of the room would be off the board.
+  OutOfBounds,
+  WallInside(Coord),
+  MonsterInside(Coord),
+  /// Rooms can only hold one chest.
+  ExtraChest(Coord),
+  NoEntrance,
+  /// All the open cells on the room's border.
+  TooManyEntrances(Vec<Coord>),
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{solver::Walls, testing::board};
+
+  fn reasons(puzzle: &Puzzle, walls: &Walls) -> Vec<FailureReason> {
+    puzzle
+      .check_solution_all(walls, false)
+      .into_iter()
+      .map(|f| f.reason)
+      .collect()
+  }
+
+  const GOOD: &[&str] = &[
+    "#####", //
+    "#...#", //
+    "#.$.#", //
+    "#...#", //
+    "##.##", //
+    "@...@",
+  ];
+
+  #[test]
+  fn good_board_passes() {
+    let (puzzle, walls) = board(GOOD);
+    assert!(puzzle.check_solution(&walls, false).is_ok());
+    assert!(puzzle.check_solution_all(&walls, false).is_empty());
+  }
+
+  #[test]
+  fn entirely_filled_with_walls() {
+    let (puzzle, walls) = board(&["###", "###"]);
+    let fail = puzzle.check_solution(&walls, false).unwrap_err();
+    assert_eq!(fail.reason, FailureReason::EntirelyFilledWithWalls);
+    assert_eq!(fail.region.len(), 6);
+  }
+
+  #[test]
+  fn wall_overlaps_filled_tile() {
+    let (puzzle, walls) = board(&["@..M"]);
+    let fail = puzzle.check_solution(&walls, false).unwrap_err();
+    assert_eq!(
+      fail.reason,
+      FailureReason::WallOverlapsFilledTile(Tile::Monster)
+    );
+    assert_eq!(fail.pos, Coord::new(3, 0));
+  }
+
+  #[test]
+  fn discontiguous_areas() {
+    let (puzzle, walls) = board(&["@.@", "###", "@.@"]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    assert_eq!(fails.len(), 1);
+    match &fails[0].reason {
+      FailureReason::DiscontiguousAreas(areas) => assert_eq!(areas.len(), 2),
+      it => panic!("wrong reason {:?}", it),
+    }
+    // The top area wins the tie, so the bottom one is the problem.
+    assert_eq!(fails[0].pos, Coord::new(0, 2));
+    assert_eq!(fails[0].region.len(), 3);
+  }
+
+  #[test]
+  fn dead_end_without_monster() {
+    let (puzzle, walls) = board(&["@.."]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    assert_eq!(fails.len(), 1);
+    assert_eq!(fails[0].reason, FailureReason::DeadEndWithoutMonster);
+    assert_eq!(fails[0].pos, Coord::new(2, 0));
+  }
+
+  #[test]
+  fn monster_without_dead_end() {
+    let (puzzle, walls) = board(&["@.@.@"]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    assert_eq!(fails.len(), 1);
+    assert_eq!(fails[0].reason, FailureReason::MonsterWithoutDeadEnd);
+    assert_eq!(fails[0].pos, Coord::new(2, 0));
+  }
+
+  #[test]
+  fn no_treasure_room() {
+    let (puzzle, walls) = board(&["#####", "@.$.@", "#####"]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    assert_eq!(fails.len(), 1);
+    match &fails[0].reason {
+      FailureReason::NoTreasureRoom(attempts) => {
+        assert!(!attempts.is_empty());
+        assert!(attempts.iter().all(|a| matches!(
+          a.rejection,
+          RoomRejection::WallInside(_) | RoomRejection::OutOfBounds
+        )));
+      }
+      it => panic!("wrong reason {:?}", it),
+    }
+    assert_eq!(fails[0].pos, Coord::new(2, 1));
+  }
+
+  #[test]
+  fn large_area_outside_of_treasure_room() {
+    let (puzzle, walls) = board(&["@..@", "#..#"]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    assert_eq!(fails.len(), 1);
+    assert_eq!(
+      fails[0].reason,
+      FailureReason::LargeAreaOutsideOfTreasureRoom([
+        Coord::new(1, 0),
+        Coord::new(2, 0),
+        Coord::new(1, 1),
+        Coord::new(2, 1),
+      ])
+    );
+  }
+
+  #[test]
+  fn row_wall_count_mismatch() {
+    let (puzzle, walls) = board(GOOD);
+    let mut side_hints = puzzle.side_hints().to_vec();
+    side_hints[2] += 1;
+    let puzzle = Puzzle::new(
+      puzzle.tiles.clone(),
+      puzzle.top_hints().to_vec(),
+      side_hints,
+    );
+    assert_eq!(
+      reasons(&puzzle, &walls),
+      vec![FailureReason::RowWallCountMismatch(2)]
+    );
+  }
+
+  #[test]
+  fn column_wall_count_mismatch() {
+    let (puzzle, walls) = board(GOOD);
+    let mut top_hints = puzzle.top_hints().to_vec();
+    top_hints[4] -= 1;
+    let puzzle = Puzzle::new(
+      puzzle.tiles.clone(),
+      top_hints,
+      puzzle.side_hints().to_vec(),
+    );
+    assert_eq!(
+      reasons(&puzzle, &walls),
+      vec![FailureReason::ColumnWallCountMismatch(4)]
+    );
+  }
+
+  #[test]
+  fn all_walls_beats_overlap() {
+    let (puzzle, walls) = board(&["M##", "###"]);
+    let fail = puzzle.check_solution(&walls, false).unwrap_err();
+    assert_eq!(fail.reason, FailureReason::EntirelyFilledWithWalls);
+    assert_eq!(
+      reasons(&puzzle, &walls),
+      vec![
+        FailureReason::EntirelyFilledWithWalls,
+        FailureReason::WallOverlapsFilledTile(Tile::Monster),
+      ]
+    );
+  }
+
+  #[test]
+  fn first_failure_is_best_of_all() {
+    for rows in [
+      &["M##", "###"][..],
+      &["..@M"],
+      &["@.@", "###", "@.@"],
+      &["@..@", "#..#"],
+      &["#####", "@.$.@", "#####"],
+      &["@....", "#.$.#", "#...M", "##.##", "@...@"],
+    ] {
+      let (puzzle, walls) = board(rows);
+      let best = puzzle
+        .check_solution_all(&walls, false)
+        .into_iter()
+        .min_by_key(|f| (f.reason.priority(), f.pos.y, f.pos.x))
+        .map(|f| f.reason);
+      let first = puzzle.check_solution(&walls, false).err().map(|f| f.reason);
+      assert_eq!(first, best, "{:?}", rows);
+    }
+  }
+
+  #[test]
+  fn first_failure_follows_priority() {
+    // The dead end at (0, 0) comes first positionally, but a walled-over
+    // monster is worse.
+    let (puzzle, walls) = board(&["..@M"]);
+    let fail = puzzle.check_solution(&walls, false).unwrap_err();
+    assert_eq!(
+      fail.reason,
+      FailureReason::WallOverlapsFilledTile(Tile::Monster)
+    );
+    assert_eq!(
+      reasons(&puzzle, &walls),
+      vec![
+        FailureReason::DeadEndWithoutMonster,
+        FailureReason::WallOverlapsFilledTile(Tile::Monster),
+      ]
+    );
+  }
+
+  // Treasure room rules, pinned against the original game.
+
+  fn room_rejections(fail: &Failure) -> Vec<RoomRejection> {
+    match &fail.reason {
+      FailureReason::NoTreasureRoom(attempts) => {
+        attempts.iter().map(|a| a.rejection.clone()).collect()
+      }
+      it => panic!("wrong reason {:?}", it),
+    }
+  }
+
+  #[test]
+  fn room_touching_board_edge() {
+    // The board edge walls off the bottom of the room.
+    let (puzzle, walls) = board(&[
+      "@.###", //
+      "#...#", //
+      "#.$.#", //
+      "#...#",
+    ]);
+    assert_eq!(reasons(&puzzle, &walls), vec![]);
+  }
+
+  #[test]
+  fn room_in_board_corner() {
+    let (puzzle, walls) = board(&[
+      "$..#@", //
+      "...#.", //
+      ".....",
+    ]);
+    assert_eq!(reasons(&puzzle, &walls), vec![]);
+  }
+
+  #[test]
+  fn room_never_spills_off_board() {
+    // Looks like a room if you pretend there's open space past the bottom.
+    let (puzzle, walls) = board(&[
+      "@.###", //
+      "#...#", //
+      "#.$.#",
+    ]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    let chest_fail = fails
+      .iter()
+      .find(|f| matches!(f.reason, FailureReason::NoTreasureRoom(_)))
+      .unwrap();
+    assert!(room_rejections(chest_fail).contains(&RoomRejection::OutOfBounds));
+  }
+
+  #[test]
+  fn room_with_two_chests() {
+    let (puzzle, walls) = board(&[
+      "#@###", //
+      "#...#", //
+      "#$.$#", //
+      "#...#", //
+      "#####",
+    ]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    let chest_fails = fails
+      .iter()
+      .filter(|f| matches!(f.reason, FailureReason::NoTreasureRoom(_)))
+      .collect::<Vec<_>>();
+    assert_eq!(chest_fails.len(), 2);
+    assert!(room_rejections(chest_fails[0])
+      .contains(&RoomRejection::ExtraChest(Coord::new(3, 2))));
+    assert!(room_rejections(chest_fails[1])
+      .contains(&RoomRejection::ExtraChest(Coord::new(1, 2))));
+  }
+
+  #[test]
+  fn room_with_monster() {
+    let (puzzle, walls) = board(&[
+      "#####", //
+      "#..@#", //
+      "#.$.#", //
+      "#...#", //
+      "##.##", //
+      "@...@",
+    ]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    let chest_fail = fails
+      .iter()
+      .find(|f| matches!(f.reason, FailureReason::NoTreasureRoom(_)))
+      .unwrap();
+    assert!(room_rejections(chest_fail)
+      .contains(&RoomRejection::MonsterInside(Coord::new(3, 1))));
+    assert!(fails
+      .iter()
+      .any(|f| f.reason == FailureReason::MonsterWithoutDeadEnd));
+  }
+
+  #[test]
+  fn room_with_two_entrances() {
+    let (puzzle, walls) = board(&[
+      "#####", //
+      "#...#", //
+      "#.$.#", //
+      "#...#", //
+      "#.#.#", //
+      "@...@",
+    ]);
+    let fails = puzzle.check_solution_all(&walls, false);
+    let chest_fail = fails
+      .iter()
+      .find(|f| matches!(f.reason, FailureReason::NoTreasureRoom(_)))
+      .unwrap();
+    assert!(room_rejections(chest_fail).contains(
+      &RoomRejection::TooManyEntrances(vec![
+        Coord::new(1, 4),
+        Coord::new(3, 4)
+      ])
+    ));
+    // And without a room, the open space is too big.
+    assert!(fails.iter().any(|f| matches!(
+      f.reason,
+      FailureReason::LargeAreaOutsideOfTreasureRoom(_)
+    )));
+  }
+
+  #[test]
+  fn room_without_entrance() {
+    let (puzzle, walls) = board(&[
+      "...", //
+      ".$.", //
+      "...",
+    ]);
+    let fail = puzzle.check_solution(&walls, false).unwrap_err();
+    assert!(room_rejections(&fail).contains(&RoomRejection::NoEntrance));
+  }
 }
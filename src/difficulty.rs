@@ -0,0 +1,138 @@
+//! Grading puzzles by which deductions it takes to solve them.
+
+use std::fmt;
+
+use crate::{solver, Level};
+
+impl Level {
+  /// Solve the level the way a person would, using the simplest technique
+  /// that makes progress each step, and score it by what was needed.
+  ///
+  /// Returns `None` if the level has no solution.
+  pub fn difficulty(&self) -> Option<Difficulty> {
+    let counts = solver::grade(self.puzzle())?;
+    let breakdown = Technique::ALL
+      .iter()
+      .zip(counts)
+      .filter(|(_, count)| *count > 0)
+      .map(|(&technique, count)| (technique, count))
+      .collect::<Vec<_>>();
+    let score = breakdown
+      .iter()
+      .map(|(technique, count)| technique.weight() * count)
+      .sum();
+    Some(Difficulty { score, breakdown })
+  }
+}
+
+/// How hard a level is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difficulty {
+  /// Each technique's weight times how many cells it worked out.
+  pub score: u32,
+  /// Only the techniques that were used, easiest first.
+  pub breakdown: Vec<(Technique, u32)>,
+}
+
+impl Difficulty {
+  /// The hardest technique the level needed.
+  pub fn hardest(&self) -> Option<Technique> {
+    self.breakdown.last().map(|(technique, _)| *technique)
+  }
+}
+
+/// Ways of working out a cell, from easiest to hardest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+  /// A line has all its walls, or needs all its unknowns.
+  HintSaturation,
+  /// Monsters need exactly one exit and corridors at least two.
+  DeadEnds,
+  /// Fitting rooms around chests, and keeping 2x2 areas inside them.
+  TreasureRooms,
+  /// Cells that can't reach the rest of the dungeon.
+  Connectivity,
+  /// Trying a cell one way and seeing the rules fall apart.
+  Lookahead,
+  /// Guessing with no way to tell straight away if it was right.
+  Backtracking,
+}
+
+impl Technique {
+  pub const ALL: [Technique; 6] = [
+    Technique::HintSaturation,
+    Technique::DeadEnds,
+    Technique::TreasureRooms,
+    Technique::Connectivity,
+    Technique::Lookahead,
+    Technique::Backtracking,
+  ];
+
+  /// How much each use of this adds to the score.
+  pub fn weight(&self) -> u32 {
+    match self {
+      Technique::HintSaturation => 1,
+      Technique::DeadEnds => 2,
+      Technique::TreasureRooms | Technique::Connectivity => 3,
+      Technique::Lookahead => 10,
+      Technique::Backtracking => 25,
+    }
+  }
+}
+
+impl fmt::Display for Technique {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let name = match self {
+      Technique::HintSaturation => "hints",
+      Technique::DeadEnds => "dead ends",
+      Technique::TreasureRooms => "treasure rooms",
+      Technique::Connectivity => "connectivity",
+      Technique::Lookahead => "lookahead",
+      Technique::Backtracking => "backtracking",
+    };
+    f.write_str(name)
+  }
+}
+
+impl fmt::Display for Difficulty {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "difficulty {}", self.score)?;
+    for (i, (technique, count)) in self.breakdown.iter().enumerate() {
+      let sep = if i == 0 { " (" } else { ", " };
+      write!(f, "{}{} {}", sep, technique, count)?;
+    }
+    if !self.breakdown.is_empty() {
+      write!(f, ")")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use aglet::Grid;
+
+  use super::*;
+  use crate::{parse_to_level, testing::BRIGHTLEAF, Puzzle};
+
+  #[test]
+  fn brightleaf_needs_no_guessing() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let difficulty = level.difficulty().unwrap();
+    assert!(difficulty.score > 0);
+    assert!(difficulty.hardest() < Some(Technique::Lookahead));
+
+    // Every cell that isn't a tile got worked out by something.
+    let tiles = level.puzzle().tiles.iter().count() as u32;
+    let deduced: u32 = difficulty.breakdown.iter().map(|(_, n)| n).sum();
+    assert_eq!(deduced, 8 * 8 - tiles);
+  }
+
+  #[test]
+  fn unsolvable_has_no_difficulty() {
+    // One open cell is a dead end with no monster
+    let puzzle = Puzzle::new(Grid::new(1, 1), vec![0], vec![0]);
+    let level = Level::new(puzzle, "Nope".to_string(), String::new());
+    assert_eq!(level.difficulty(), None);
+  }
+}
@@ -0,0 +1,328 @@
+//! Procedural dungeon generation.
+//!
+//! We carve a dungeon into solid rock, read the hints off of it, and then
+//! throw the walls away.
+
+use aglet::{Coord, Grid};
+
+use crate::{
+  checker::room_border, solver::Walls, Level, Puzzle, Solution, Tile,
+};
+
+/// How many dungeons we carve before giving up.
+const MAX_ATTEMPTS: u32 = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+  pub width: u32,
+  pub height: u32,
+  pub seed: u64,
+  /// How many 3x3 treasure rooms to try to place.
+  pub treasure_rooms: u32,
+  /// Keep generating until the solver finds exactly one solution.
+  ///
+  /// Turn this on for anything handed to a player.
+  pub require_unique: bool,
+}
+
+impl GeneratorConfig {
+  pub fn new(width: u32, height: u32, seed: u64) -> Self {
+    Self {
+      width,
+      height,
+      seed,
+      treasure_rooms: 1,
+      require_unique: true,
+    }
+  }
+}
+
+/// Generate a level. The same config always makes the same level.
+///
+/// Returns `None` if nothing valid turned up, which happens for empty boards
+/// and boards too small to fit the requested rooms.
+pub fn generate(config: &GeneratorConfig) -> Option<Level> {
+  if config.width == 0 || config.height == 0 {
+    return None;
+  }
+  let mut rng = SplitMix64(config.seed);
+  for _ in 0..MAX_ATTEMPTS {
+    let dungeon = match Dungeon::carve(config, &mut rng) {
+      Some(it) => it,
+      None => continue,
+    };
+    let puzzle = dungeon.to_puzzle();
+    if puzzle.check_solution(&dungeon, false).is_err() {
+      continue;
+    }
+    if config.require_unique && puzzle.solutions(2).len() != 1 {
+      continue;
+    }
+
+    let title = format!("Generated Dungeon #{}", config.seed);
+    let comment = format!(
+      "Generated at {}x{} with seed {}.",
+      config.width, config.height, config.seed
+    );
+    let mut level = Level::new(puzzle, title, comment);
+    level.set_solution(Some(Walls::new(
+      dungeon.width,
+      dungeon.height,
+      dungeon.open.iter().map(|&open| !open).collect(),
+    )));
+    return Some(level);
+  }
+  None
+}
+
+struct Dungeon {
+  width: u32,
+  height: u32,
+  open: Vec<bool>,
+  tiles: Grid<Tile>,
+}
+
+impl Dungeon {
+  fn carve(config: &GeneratorConfig, rng: &mut SplitMix64) -> Option<Self> {
+    let (width, height) = (config.width, config.height);
+    let mut dungeon = Dungeon {
+      width,
+      height,
+      open: vec![false; (width * height) as usize],
+      tiles: Grid::new(width, height),
+    };
+    // Cells corridors must never open up, so rooms keep one entrance.
+    let mut forbidden = vec![false; dungeon.open.len()];
+    let mut active = Vec::new();
+
+    for _ in 0..config.treasure_rooms {
+      if width < 3 || height < 3 {
+        return None;
+      }
+      let cx = rng.below(width - 2);
+      let cy = rng.below(height - 2);
+      let room = (cy..cy + 3)
+        .flat_map(|y| (cx..cx + 3).map(move |x| Coord::new(x, y)))
+        .collect::<Vec<_>>();
+      if room
+        .iter()
+        .any(|&c| dungeon.is_open(c) || forbidden[dungeon.idx(c)])
+      {
+        return None;
+      }
+
+      let border = room_border(cx, cy)
+        .into_iter()
+        .filter_map(|(x, y)| dungeon.coord(x, y))
+        .collect::<Vec<_>>();
+      let entrances = border
+        .iter()
+        .copied()
+        .filter(|&c| !forbidden[dungeon.idx(c)])
+        .collect::<Vec<_>>();
+      if entrances.is_empty() {
+        return None;
+      }
+      let entrance = entrances[rng.below(entrances.len() as u32) as usize];
+
+      for &c in room.iter() {
+        dungeon.set_open(c);
+      }
+      for &c in border.iter() {
+        forbidden[dungeon.idx(c)] = true;
+      }
+      // The room's diagonal corners would let a corridor hug the room.
+      for (x, y) in [
+        (cx as i32 - 1, cy as i32 - 1),
+        (cx as i32 + 3, cy as i32 - 1),
+        (cx as i32 - 1, cy as i32 + 3),
+        (cx as i32 + 3, cy as i32 + 3),
+      ] {
+        if let Some(c) = dungeon.coord(x, y) {
+          forbidden[dungeon.idx(c)] = true;
+        }
+      }
+      dungeon.set_open(entrance);
+      active.push(entrance);
+
+      let chest = room[rng.below(9) as usize];
+      dungeon.tiles.insert(chest, Tile::TreasureChest);
+    }
+
+    if active.is_empty() {
+      let start = Coord::new(rng.below(width), rng.below(height));
+      dungeon.set_open(start);
+      active.push(start);
+    }
+
+    // Growing-tree maze: extend from a random active cell into rock that
+    // only touches that cell, so corridors stay one wide.
+    while !active.is_empty() {
+      let pick = if rng.below(2) == 0 {
+        active.len() - 1
+      } else {
+        rng.below(active.len() as u32) as usize
+      };
+      let here = active[pick];
+
+      let mut options = here
+        .neighbors4()
+        .into_iter()
+        .filter(|&n| {
+          n.x < width
+            && n.y < height
+            && !dungeon.is_open(n)
+            && !forbidden[dungeon.idx(n)]
+            && dungeon.open_neighbors(n) == 1
+        })
+        .collect::<Vec<_>>();
+      if options.is_empty() {
+        active.swap_remove(pick);
+        continue;
+      }
+      let next = options.swap_remove(rng.below(options.len() as u32) as usize);
+      dungeon.set_open(next);
+      active.push(next);
+    }
+
+    // Mostly-rock dungeons make for boring puzzles.
+    let open_count = dungeon.open.iter().filter(|&&o| o).count() as u32;
+    if open_count * 3 < width * height {
+      return None;
+    }
+
+    // Monsters go in every dead end.
+    for y in 0..height {
+      for x in 0..width {
+        let c = Coord::new(x, y);
+        if dungeon.is_open(c)
+          && dungeon.tiles.get(c).is_none()
+          && dungeon.open_neighbors(c) <= 1
+        {
+          dungeon.tiles.insert(c, Tile::Monster);
+        }
+      }
+    }
+
+    Some(dungeon)
+  }
+
+  fn to_puzzle(&self) -> Puzzle {
+    let top_hints = (0..self.width)
+      .map(|x| {
+        (0..self.height)
+          .filter(|&y| self.is_wall(Coord::new(x, y)))
+          .count() as u8
+      })
+      .collect();
+    let side_hints = (0..self.height)
+      .map(|y| {
+        (0..self.width)
+          .filter(|&x| self.is_wall(Coord::new(x, y)))
+          .count() as u8
+      })
+      .collect();
+    Puzzle::new(self.tiles.clone(), top_hints, side_hints)
+  }
+
+  fn idx(&self, coord: Coord) -> usize {
+    (coord.y * self.width + coord.x) as usize
+  }
+
+  fn coord(&self, x: i32, y: i32) -> Option<Coord> {
+    if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+      None
+    } else {
+      Some(Coord::new(x as u32, y as u32))
+    }
+  }
+
+  fn is_open(&self, coord: Coord) -> bool {
+    coord.x < self.width && coord.y < self.height && self.open[self.idx(coord)]
+  }
+
+  fn set_open(&mut self, coord: Coord) {
+    let idx = self.idx(coord);
+    self.open[idx] = true;
+  }
+
+  fn open_neighbors(&self, coord: Coord) -> usize {
+    coord
+      .neighbors4()
+      .into_iter()
+      .filter(|&n| self.is_open(n))
+      .count()
+  }
+}
+
+impl Solution for Dungeon {
+  fn is_wall(&self, coord: Coord) -> bool {
+    !self.is_open(coord)
+  }
+}
+
+/// Tiny deterministic RNG, so seeds mean the same thing forever.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+
+  /// Uniform-ish number in `0..n`. `n` must not be 0.
+  fn below(&mut self, n: u32) -> u32 {
+    (self.next() % n as u64) as u32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::solver::Uniqueness;
+
+  #[test]
+  fn same_seed_same_level() {
+    let config = GeneratorConfig::new(8, 8, 42);
+    let a = generate(&config).unwrap();
+    let b = generate(&config).unwrap();
+    assert_eq!(a.to_ttc_string(), b.to_ttc_string());
+
+    let other = generate(&GeneratorConfig::new(8, 8, 43)).unwrap();
+    assert_ne!(a.to_ttc_string(), other.to_ttc_string());
+  }
+
+  #[test]
+  fn generated_levels_are_uniquely_solvable() {
+    for seed in 0..10 {
+      let mut config = GeneratorConfig::new(7, 6, seed);
+      config.treasure_rooms = (seed % 2) as u32;
+      let level = generate(&config).unwrap();
+      let puzzle = level.puzzle();
+      let solution = level.solution().unwrap();
+      assert!(
+        puzzle.check_solution(solution, false).is_ok(),
+        "seed {}",
+        seed
+      );
+      match puzzle.uniqueness() {
+        Uniqueness::Unique(walls) => assert_eq!(&walls, solution),
+        other => panic!("seed {}: {:?}", seed, other),
+      }
+    }
+  }
+
+  #[test]
+  fn empty_boards_give_up() {
+    for (width, height) in [(0, 5), (5, 0), (0, 0)] {
+      let mut config = GeneratorConfig::new(width, height, 1);
+      config.treasure_rooms = 0;
+      assert!(generate(&config).is_none());
+    }
+    // No room for a treasure room
+    assert!(generate(&GeneratorConfig::new(2, 8, 1)).is_none());
+  }
+}
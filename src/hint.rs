@@ -0,0 +1,182 @@
+//! Hints for players who are stuck, using the solver's deductions.
+
+use std::fmt;
+
+use aglet::Coord;
+
+use crate::{
+  board::{Board, Cell},
+  solver, Puzzle,
+};
+
+impl Puzzle {
+  /// Find a cell the rules force, given what's filled in so far, and say
+  /// why.
+  ///
+  /// Returns `None` if nothing can be deduced without guessing, or if the
+  /// board already breaks the rules somewhere.
+  pub fn hint(&self, board: &Board) -> Option<Hint> {
+    solver::first_deduction(self, board)
+  }
+}
+
+/// One deduction: `coord` has to be `cell`, because of `reason`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+  pub coord: Coord,
+  /// Either [`Cell::Wall`] or [`Cell::Open`].
+  pub cell: Cell,
+  pub reason: Reason,
+}
+
+/// A row or a column. These are zero-indexed, but get printed one-indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+  Row(u32),
+  Column(u32),
+}
+
+/// The rule behind a deduction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+  /// The line already has all its walls, so the rest is open.
+  LineFull(Line),
+  /// The line needs every cell that's left to be a wall.
+  LineNeedsWalls(Line),
+  /// The monster here already has its one way out.
+  MonsterHasExit(Coord),
+  /// The monster here only has one way out left.
+  MonsterNeedsExit(Coord),
+  /// The open cell here only has two ways left to go, and needs both.
+  CorridorNeedsExits(Coord),
+  /// Opening the cell would make a dead end with no monster.
+  WouldBeDeadEnd,
+  /// Every room the chest here could have covers the cell.
+  RoomWindow(Coord),
+  /// The room around the chest here already has its entrance.
+  RoomHasEntrance(Coord),
+  /// The room around the chest here has one place left for an entrance.
+  RoomNeedsEntrance(Coord),
+  /// Opening the cell would make a 2x2 area outside any treasure room.
+  LargeArea,
+  /// The cell can't connect to the rest of the dungeon.
+  Unreachable,
+}
+
+impl fmt::Display for Line {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Line::Row(y) => write!(f, "row {}", y + 1),
+      Line::Column(x) => write!(f, "column {}", x + 1),
+    }
+  }
+}
+
+impl fmt::Display for Reason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Reason::LineFull(line) => {
+        write!(f, "{} already has all its walls, so the rest is open", line)
+      }
+      Reason::LineNeedsWalls(line) => {
+        write!(f, "{} needs every cell that's left to be a wall", line)
+      }
+      Reason::MonsterHasExit(at) => write!(
+        f,
+        "the monster at {} already has its way out, so the rest are walls",
+        at
+      ),
+      Reason::MonsterNeedsExit(at) => {
+        write!(f, "the monster at {} has only one way out left", at)
+      }
+      Reason::CorridorNeedsExits(at) => write!(
+        f,
+        "the corridor at {} only has two ways to go, and needs both",
+        at
+      ),
+      Reason::WouldBeDeadEnd => {
+        write!(f, "this would be a dead end with no monster")
+      }
+      Reason::RoomWindow(at) => {
+        write!(f, "every room the chest at {} could have covers this", at)
+      }
+      Reason::RoomHasEntrance(at) => write!(
+        f,
+        "the room around the chest at {} already has its entrance",
+        at
+      ),
+      Reason::RoomNeedsEntrance(at) => write!(
+        f,
+        "the room around the chest at {} has one place left for an entrance",
+        at
+      ),
+      Reason::LargeArea => {
+        write!(
+          f,
+          "opening this would make a 2x2 area outside a treasure room"
+        )
+      }
+      Reason::Unreachable => {
+        write!(f, "this can't connect to the rest of the dungeon")
+      }
+    }
+  }
+}
+
+impl fmt::Display for Hint {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let what = match self.cell {
+      Cell::Wall => "a wall",
+      _ => "open",
+    };
+    write!(f, "{} must be {}: {}", self.coord, what, self.reason)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{parse_to_level, testing::BRIGHTLEAF, Solution};
+
+  #[test]
+  fn hints_solve_brightleaf() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let puzzle = level.puzzle();
+    let solution = level.solution().unwrap();
+    let mut board = Board::new(puzzle.clone());
+
+    // Brightleaf needs no guessing, so following hints should get all the
+    // way there, and every hint should agree with the real answer.
+    while let Some(hint) = puzzle.hint(&board) {
+      assert_eq!(hint.cell == Cell::Wall, solution.is_wall(hint.coord));
+      assert_eq!(board.get(hint.coord), Cell::Unknown);
+      board.set(hint.coord, hint.cell);
+    }
+    assert!(puzzle.check_solution(&board, false).is_ok());
+  }
+
+  #[test]
+  fn lines_count_from_one() {
+    assert_eq!(
+      Reason::LineFull(Line::Row(0)).to_string(),
+      "row 1 already has all its walls, so the rest is open"
+    );
+    assert_eq!(Line::Column(7).to_string(), "column 8");
+  }
+
+  #[test]
+  fn no_hint_for_broken_board() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let puzzle = level.puzzle();
+    let mut board = Board::new(puzzle.clone());
+    // The top row only wants 3 walls.
+    for x in 0..5 {
+      board.set(Coord::new(x, 0), Cell::Wall);
+    }
+    assert_eq!(puzzle.hint(&board), None);
+    assert!(!puzzle.is_consistent(&board));
+
+    board.clear_all();
+    assert!(puzzle.is_consistent(&board));
+  }
+}
@@ -0,0 +1,617 @@
+//! A checker that keeps its state up to date as single cells change,
+//! instead of starting from scratch every time.
+//!
+//! Row and column counts, dead ends, tile overlaps and 2x2 blocks are
+//! updated on every toggle. Treasure rooms and connectivity are only
+//! recomputed when a toggle could have changed them, and only when asked.
+//! Failures are put together from all that, so they're cheap to ask for too.
+
+use aglet::Coord;
+
+use crate::{
+  checker::{Failure, FailureReason},
+  Puzzle, Solution, Tile,
+};
+
+#[derive(Debug, Clone)]
+pub struct IncrementalChecker {
+  puzzle: Puzzle,
+  walls: Vec<bool>,
+
+  row_counts: Vec<u32>,
+  col_counts: Vec<u32>,
+  /// How many rows and columns disagree with their hints.
+  mismatched_lines: usize,
+
+  /// Walls on top of monsters or chests.
+  overlaps: usize,
+  open_count: usize,
+  /// Open cells breaking the dead end <=> monster rule.
+  bad_dead_ends: Vec<bool>,
+  bad_dead_end_count: usize,
+  /// Indexed by top-left corner; the last row and column are never set.
+  open_blocks: Vec<bool>,
+  open_block_count: usize,
+
+  chests: Vec<Coord>,
+  /// Top-left corner of each chest's room. `None` when it needs rechecking.
+  rooms: Vec<Option<Option<Coord>>>,
+  /// `None` when it needs rechecking.
+  connected: Option<bool>,
+}
+
+impl IncrementalChecker {
+  /// Start checking from an existing set of walls.
+  pub fn new<S: Solution>(puzzle: &Puzzle, solution: &S) -> Self {
+    let (width, height) = (puzzle.width(), puzzle.height());
+    let size = (width * height) as usize;
+    let chests = (0..height)
+      .flat_map(|y| (0..width).map(move |x| Coord::new(x, y)))
+      .filter(|&c| puzzle.get_tile(c) == Some(Tile::TreasureChest))
+      .collect::<Vec<_>>();
+
+    let mut this = Self {
+      puzzle: puzzle.clone(),
+      walls: vec![false; size],
+      row_counts: vec![0; height as usize],
+      col_counts: vec![0; width as usize],
+      mismatched_lines: 0,
+      overlaps: 0,
+      open_count: size,
+      bad_dead_ends: vec![false; size],
+      bad_dead_end_count: 0,
+      open_blocks: vec![false; size],
+      open_block_count: 0,
+      rooms: vec![None; chests.len()],
+      chests,
+      connected: None,
+    };
+
+    // Start from an open board with everything tallied, then wall it up
+    // through the normal path.
+    this.mismatched_lines = this
+      .puzzle
+      .side_hints()
+      .iter()
+      .chain(this.puzzle.top_hints())
+      .filter(|&&h| h != 0)
+      .count();
+    for y in 0..height {
+      for x in 0..width {
+        let coord = Coord::new(x, y);
+        this.update_dead_end(coord);
+        this.update_block(coord);
+      }
+    }
+    for y in 0..height {
+      for x in 0..width {
+        let coord = Coord::new(x, y);
+        if solution.is_wall(coord) {
+          this.set_wall(coord, true);
+        }
+      }
+    }
+    this
+  }
+
+  pub fn puzzle(&self) -> &Puzzle {
+    &self.puzzle
+  }
+
+  /// Flip the cell between wall and open.
+  pub fn apply_toggle(&mut self, coord: Coord) {
+    let wall = self.is_wall(coord);
+    self.set_wall(coord, !wall);
+  }
+
+  /// Set whether the cell is a wall. Does nothing if it already is.
+  pub fn set_wall(&mut self, coord: Coord, wall: bool) {
+    let idx = match self.idx(coord) {
+      Some(it) => it,
+      None => return,
+    };
+    if self.walls[idx] == wall {
+      return;
+    }
+
+    let open_neighbors = self.open_neighbors(coord);
+    let was_connected = self.connected;
+    self.walls[idx] = wall;
+
+    // Hints
+    let delta = |count: &mut u32| {
+      if wall {
+        *count += 1;
+      } else {
+        *count -= 1;
+      }
+    };
+    // Lines without a hint can't disagree with it
+    let side = self.puzzle.side_hints().get(coord.y as usize);
+    let top = self.puzzle.top_hints().get(coord.x as usize);
+    let matches = |count: u32, hint: Option<&u8>| {
+      hint.is_none_or(|&hint| count == hint as u32)
+    };
+    let row_was = matches(self.row_counts[coord.y as usize], side);
+    let col_was = matches(self.col_counts[coord.x as usize], top);
+    delta(&mut self.row_counts[coord.y as usize]);
+    delta(&mut self.col_counts[coord.x as usize]);
+    let row_is = matches(self.row_counts[coord.y as usize], side);
+    let col_is = matches(self.col_counts[coord.x as usize], top);
+    for (was, is) in [(row_was, row_is), (col_was, col_is)] {
+      match (was, is) {
+        (true, false) => self.mismatched_lines += 1,
+        (false, true) => self.mismatched_lines -= 1,
+        _ => {}
+      }
+    }
+
+    // Tiles
+    if self.puzzle.get_tile(coord).is_some() {
+      if wall {
+        self.overlaps += 1;
+      } else {
+        self.overlaps -= 1;
+      }
+    }
+    if wall {
+      self.open_count -= 1;
+    } else {
+      self.open_count += 1;
+    }
+
+    // Dead ends around here
+    self.update_dead_end(coord);
+    for n in coord.neighbors4() {
+      if self.idx(n).is_some() {
+        self.update_dead_end(n);
+      }
+    }
+
+    // The up to 4 blocks this cell is part of
+    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+      if coord.x >= dx && coord.y >= dy {
+        self.update_block(Coord::new(coord.x - dx, coord.y - dy));
+      }
+    }
+
+    // Any room whose inside or border this touches
+    for (i, chest) in self.chests.iter().enumerate() {
+      if chest.x.abs_diff(coord.x) <= 3 && chest.y.abs_diff(coord.y) <= 3 {
+        self.rooms[i] = None;
+      }
+    }
+
+    // Connectivity only needs a full recheck if this could have split or
+    // joined areas.
+    self.connected = match (was_connected, wall) {
+      // Opening a cell next to the one area keeps it whole.
+      (Some(true), false) if open_neighbors > 0 || self.open_count == 1 => {
+        Some(true)
+      }
+      // Opening an island makes a second area.
+      (Some(true), false) => Some(false),
+      // Walling a leaf or an island doesn't split anything.
+      (Some(true), true) if open_neighbors <= 1 => Some(true),
+      _ => None,
+    };
+  }
+
+  /// Whether the current walls pass [`Puzzle::check_solution`].
+  pub fn is_solved(&mut self) -> bool {
+    if self.overlaps != 0
+      || self.open_count == 0
+      || self.mismatched_lines != 0
+      || self.bad_dead_end_count != 0
+    {
+      return false;
+    }
+
+    for i in 0..self.chests.len() {
+      if self.room(i).is_none() {
+        return false;
+      }
+    }
+    if self.open_block_count != 0 && !self.blocks_all_claimed() {
+      return false;
+    }
+
+    self.is_connected()
+  }
+
+  /// Every problem with the current walls, the same as
+  /// [`Puzzle::check_solution_all`] finds.
+  ///
+  /// This works from what's already tracked, so it's fine to call after
+  /// every edit. Only a board split into several areas needs a walk over
+  /// every open cell, to list them.
+  pub fn failures(&mut self) -> Vec<Failure> {
+    let (width, height) = (self.puzzle.width(), self.puzzle.height());
+    let mut out = Vec::new();
+
+    if self.open_count == 0 {
+      let everything = (0..height)
+        .flat_map(|y| (0..width).map(move |x| Coord::new(x, y)))
+        .collect::<Vec<_>>();
+      out.push(if everything.is_empty() {
+        Failure::new(Coord::new(0, 0), FailureReason::EntirelyFilledWithWalls)
+      } else {
+        Failure::new_region(FailureReason::EntirelyFilledWithWalls, everything)
+      });
+    }
+    if self.overlaps != 0 {
+      for (coord, &tile) in self.puzzle.tiles.iter() {
+        if self.is_wall(coord) {
+          out.push(Failure::new(
+            coord,
+            FailureReason::WallOverlapsFilledTile(tile),
+          ));
+        }
+      }
+    }
+
+    if self.open_count != 0 && !self.is_connected() {
+      let areas = self.areas();
+      // The biggest area is the "real" one, the earliest on ties
+      let main = areas
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, area)| area.len())
+        .map(|(i, _)| i)
+        .unwrap();
+      let stragglers = areas
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != main)
+        .flat_map(|(_, area)| area.iter().copied())
+        .collect();
+      out.push(Failure::new_region(
+        FailureReason::DiscontiguousAreas(areas),
+        stragglers,
+      ));
+    }
+
+    if self.bad_dead_end_count != 0 {
+      for (idx, _) in self.bad_dead_ends.iter().enumerate().filter(|(_, b)| **b)
+      {
+        let coord = Coord::new(idx as u32 % width, idx as u32 / width);
+        let reason = if self.puzzle.get_tile(coord) == Some(Tile::Monster) {
+          FailureReason::MonsterWithoutDeadEnd
+        } else {
+          FailureReason::DeadEndWithoutMonster
+        };
+        out.push(Failure::new(coord, reason));
+      }
+    }
+
+    let mut claimed = vec![false; self.walls.len()];
+    for i in 0..self.chests.len() {
+      match self.room(i) {
+        Some(corner) => {
+          for y in corner.y..corner.y + 3 {
+            for x in corner.x..corner.x + 3 {
+              claimed[(y * width + x) as usize] = true;
+            }
+          }
+        }
+        None => {
+          if let Err(fail) =
+            self.puzzle.check_chest(self, self.chests[i], false)
+          {
+            out.push(fail);
+          }
+        }
+      }
+    }
+    for (idx, _) in self.open_blocks.iter().enumerate().filter(|(_, o)| **o) {
+      let corner = Coord::new(idx as u32 % width, idx as u32 / width);
+      let block = [
+        corner,
+        Coord::new(corner.x + 1, corner.y),
+        Coord::new(corner.x, corner.y + 1),
+        Coord::new(corner.x + 1, corner.y + 1),
+      ];
+      if !block.iter().all(|&c| claimed[self.idx(c).unwrap()]) {
+        out.push(Failure::new_region(
+          FailureReason::LargeAreaOutsideOfTreasureRoom(block),
+          block.to_vec(),
+        ));
+      }
+    }
+
+    for (y, &hint) in self.puzzle.side_hints().iter().enumerate() {
+      if self.row_counts.get(y).copied().unwrap_or(0) != hint as u32 {
+        out.push(Failure::new_region(
+          FailureReason::RowWallCountMismatch(y as u32),
+          (0..width).map(|x| Coord::new(x, y as u32)).collect(),
+        ));
+      }
+    }
+    for (x, &hint) in self.puzzle.top_hints().iter().enumerate() {
+      if self.col_counts.get(x).copied().unwrap_or(0) != hint as u32 {
+        out.push(Failure::new_region(
+          FailureReason::ColumnWallCountMismatch(x as u32),
+          (0..height).map(|y| Coord::new(x as u32, y)).collect(),
+        ));
+      }
+    }
+
+    out.sort_by_key(|f| (f.pos.y, f.pos.x, f.reason.priority()));
+    out
+  }
+
+  pub fn row_wall_count(&self, y: u32) -> u32 {
+    self.row_counts[y as usize]
+  }
+
+  pub fn column_wall_count(&self, x: u32) -> u32 {
+    self.col_counts[x as usize]
+  }
+
+  fn idx(&self, coord: Coord) -> Option<usize> {
+    if coord.x < self.puzzle.width() && coord.y < self.puzzle.height() {
+      Some((coord.y * self.puzzle.width() + coord.x) as usize)
+    } else {
+      None
+    }
+  }
+
+  fn is_open(&self, coord: Coord) -> bool {
+    match self.idx(coord) {
+      Some(idx) => !self.walls[idx],
+      None => false,
+    }
+  }
+
+  fn open_neighbors(&self, coord: Coord) -> usize {
+    coord
+      .neighbors4()
+      .into_iter()
+      .filter(|&n| self.is_open(n))
+      .count()
+  }
+
+  fn update_dead_end(&mut self, coord: Coord) {
+    let idx = self.idx(coord).unwrap();
+    let bad = !self.walls[idx] && {
+      let dead_end = self.open_neighbors(coord) <= 1;
+      let monster = self.puzzle.get_tile(coord) == Some(Tile::Monster);
+      dead_end != monster
+    };
+    match (self.bad_dead_ends[idx], bad) {
+      (false, true) => self.bad_dead_end_count += 1,
+      (true, false) => self.bad_dead_end_count -= 1,
+      _ => {}
+    }
+    self.bad_dead_ends[idx] = bad;
+  }
+
+  /// Recheck the 2x2 block with this top-left corner.
+  fn update_block(&mut self, corner: Coord) {
+    let idx = self.idx(corner).unwrap();
+    let open = [(0, 0), (1, 0), (0, 1), (1, 1)]
+      .iter()
+      .all(|&(dx, dy)| self.is_open(Coord::new(corner.x + dx, corner.y + dy)));
+    match (self.open_blocks[idx], open) {
+      (false, true) => self.open_block_count += 1,
+      (true, false) => self.open_block_count -= 1,
+      _ => {}
+    }
+    self.open_blocks[idx] = open;
+  }
+
+  /// The corner of the `i`th chest's room, rechecking it if needed.
+  fn room(&mut self, i: usize) -> Option<Coord> {
+    if let Some(room) = self.rooms[i] {
+      return room;
+    }
+    let chest = self.chests[i];
+    let (width, height) = (self.puzzle.width(), self.puzzle.height());
+    let mut found = None;
+    'search: for cy in chest.y.saturating_sub(2)..=chest.y {
+      for cx in chest.x.saturating_sub(2)..=chest.x {
+        if cx + 3 > width || cy + 3 > height {
+          continue;
+        }
+        let corner = Coord::new(cx, cy);
+        if self.puzzle.check_room(self, corner, chest).is_ok() {
+          found = Some(corner);
+          break 'search;
+        }
+      }
+    }
+    self.rooms[i] = Some(found);
+    found
+  }
+
+  fn blocks_all_claimed(&mut self) -> bool {
+    let rooms = (0..self.chests.len())
+      .filter_map(|i| self.room(i))
+      .collect::<Vec<_>>();
+    let width = self.puzzle.width();
+    self.open_blocks.iter().enumerate().all(|(idx, &open)| {
+      if !open {
+        return true;
+      }
+      let (bx, by) = (idx as u32 % width, idx as u32 / width);
+      rooms.iter().any(|room| {
+        room.x <= bx && bx < room.x + 2 && room.y <= by && by < room.y + 2
+      })
+    })
+  }
+
+  fn is_connected(&mut self) -> bool {
+    if let Some(connected) = self.connected {
+      return connected;
+    }
+
+    let start = match self.walls.iter().position(|&w| !w) {
+      Some(it) => it as u32,
+      None => {
+        self.connected = Some(true);
+        return true;
+      }
+    };
+    let width = self.puzzle.width();
+    let mut seen = vec![false; self.walls.len()];
+    let mut todo = vec![Coord::new(start % width, start / width)];
+    let mut reached = 0;
+    while let Some(here) = todo.pop() {
+      let idx = self.idx(here).unwrap();
+      if std::mem::replace(&mut seen[idx], true) {
+        continue;
+      }
+      reached += 1;
+      for n in here.neighbors4() {
+        if self.is_open(n) {
+          todo.push(n);
+        }
+      }
+    }
+
+    let connected = reached == self.open_count;
+    self.connected = Some(connected);
+    connected
+  }
+
+  /// The open cells split into 4-connected areas, in the same order as the
+  /// full checker lists them.
+  fn areas(&self) -> Vec<Vec<Coord>> {
+    let width = self.puzzle.width();
+    let mut seen = vec![false; self.walls.len()];
+    let mut out = Vec::new();
+    for start in 0..self.walls.len() {
+      if self.walls[start] || seen[start] {
+        continue;
+      }
+      let mut area = Vec::new();
+      let mut todo =
+        vec![Coord::new(start as u32 % width, start as u32 / width)];
+      while let Some(here) = todo.pop() {
+        let idx = self.idx(here).unwrap();
+        if !std::mem::replace(&mut seen[idx], true) {
+          area.push(here);
+          for n in here.neighbors4() {
+            if self.is_open(n) {
+              todo.push(n);
+            }
+          }
+        }
+      }
+      out.push(area);
+    }
+    out
+  }
+}
+
+impl Solution for IncrementalChecker {
+  fn is_wall(&self, coord: Coord) -> bool {
+    match self.idx(coord) {
+      Some(idx) => self.walls[idx],
+      None => false,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    parse_to_level,
+    testing::{random_board, Lcg, BRIGHTLEAF},
+  };
+
+  /// Check everything the incremental checker says against a full check.
+  fn assert_agrees(checker: &mut IncrementalChecker, puzzle: &Puzzle) {
+    let expected = puzzle.check_solution_all(checker, false);
+    let found = checker.failures();
+    let summary = |fails: &[Failure]| {
+      fails
+        .iter()
+        .map(|f| (f.pos, f.reason.clone(), f.region.clone()))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(summary(&found), summary(&expected));
+    assert_eq!(checker.is_solved(), expected.is_empty());
+  }
+
+  #[test]
+  fn agrees_with_full_checker() {
+    let mut rng = Lcg(12345);
+    let mut solved = 0;
+    for _ in 0..300 {
+      let (width, height) = (3 + rng.below(5), 3 + rng.below(5));
+      let (puzzle, walls) = random_board(&mut rng, width, height);
+      let mut checker = IncrementalChecker::new(&puzzle, &walls);
+      if checker.is_solved() {
+        solved += 1;
+      }
+      assert_agrees(&mut checker, &puzzle);
+
+      // and a few steps away from where it started
+      for _ in 0..3 {
+        let coord = Coord::new(rng.below(width), rng.below(height));
+        checker.apply_toggle(coord);
+        assert_agrees(&mut checker, &puzzle);
+      }
+    }
+    // Make sure both answers actually came up
+    assert!(0 < solved && solved < 300, "{} solved", solved);
+  }
+
+  #[test]
+  fn walk_from_brightleaf() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let puzzle = level.puzzle();
+    let mut checker =
+      IncrementalChecker::new(puzzle, level.solution().unwrap());
+    assert!(checker.is_solved());
+
+    let mut rng = Lcg(777);
+    for _ in 0..500 {
+      let coord =
+        Coord::new(rng.below(puzzle.width()), rng.below(puzzle.height()));
+      checker.apply_toggle(coord);
+      assert_agrees(&mut checker, puzzle);
+    }
+  }
+
+  #[test]
+  fn fill_in_from_empty() {
+    let mut rng = Lcg(99);
+    for _ in 0..50 {
+      let (puzzle, walls) = random_board(&mut rng, 6, 5);
+      let mut checker = IncrementalChecker::new(&puzzle, &Empty);
+      for y in 0..puzzle.height() {
+        for x in 0..puzzle.width() {
+          let coord = Coord::new(x, y);
+          checker.set_wall(coord, walls.is_wall(coord));
+        }
+      }
+      assert_agrees(&mut checker, &puzzle);
+      assert_eq!(
+        checker.is_solved(),
+        puzzle.check_solution(&walls, false).is_ok()
+      );
+    }
+  }
+
+  #[test]
+  fn missing_hints_dont_panic() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let mut puzzle = level.puzzle().clone();
+    puzzle.top_hints.truncate(3);
+    puzzle.side_hints.truncate(3);
+    let mut checker = IncrementalChecker::new(&puzzle, &Empty);
+    checker.apply_toggle(Coord::new(7, 7));
+    assert_agrees(&mut checker, &puzzle);
+  }
+
+  struct Empty;
+  impl Solution for Empty {
+    fn is_wall(&self, _: Coord) -> bool {
+      false
+    }
+  }
+}
@@ -1,19 +1,38 @@
+pub mod bitboard;
+pub mod board;
 pub mod checker;
+pub mod difficulty;
+pub mod generator;
+pub mod hint;
+pub mod incremental;
 mod parse;
+mod serialize;
+pub mod solver;
+#[cfg(test)]
+mod testing;
+pub mod validate;
 
-pub use parse::parse_to_level;
+pub use parse::{parse_to_level, ParseError, ParseErrorKind};
 
 use aglet::{Coord, Grid};
+use solver::Walls;
 
 #[derive(Debug, Clone)]
 pub struct Level {
   puzzle: Puzzle,
   title: String,
+  comment: String,
+  solution: Option<Walls>,
 }
 
 impl Level {
-  pub fn new(puzzle: Puzzle, title: String) -> Self {
-    Self { puzzle, title }
+  pub fn new(puzzle: Puzzle, title: String, comment: String) -> Self {
+    Self {
+      puzzle,
+      title,
+      comment,
+      solution: None,
+    }
   }
 
   pub fn puzzle(&self) -> &Puzzle {
@@ -23,6 +42,20 @@ impl Level {
   pub fn title(&self) -> &str {
     &self.title
   }
+
+  /// The free text between the title and the `---`.
+  pub fn comment(&self) -> &str {
+    &self.comment
+  }
+
+  /// The known-good solution stored alongside the puzzle, if any.
+  pub fn solution(&self) -> Option<&Walls> {
+    self.solution.as_ref()
+  }
+
+  pub fn set_solution(&mut self, solution: Option<Walls>) {
+    self.solution = solution;
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -1,57 +1,165 @@
+use std::{fmt, ops::Range};
+
 use aglet::{Coord, Grid};
 use nom::{
   branch::alt,
-  bytes::complete::{take, take_until, take_while},
+  bytes::complete::{take, take_until},
   character::complete::{
-    char, line_ending, multispace0, not_line_ending, satisfy, space0,
+    char, digit1, line_ending, not_line_ending, satisfy, space0,
   },
-  combinator::{eof, map, opt, value},
+  combinator::{map, map_res, opt},
   error::{context, VerboseError},
-  multi::{count, many0},
-  sequence::{terminated, tuple},
-  Finish, IResult, Parser,
+  multi::many0,
+  sequence::{delimited, terminated, tuple},
+  IResult, Offset, Parser,
 };
 
-use crate::{Level, Puzzle, Tile};
+use crate::{solver::Walls, Level, Puzzle, Tile};
 
 /// Parse a string into a level.
-pub fn parse_to_level(s: &str) -> Result<Level, VerboseError<&str>> {
-  let (s, level) = level(s).finish()?;
-  debug_assert_eq!(s, "");
+///
+/// Hints are single digits, or bracketed like `[12]` when they're bigger.
+/// The grid may be followed by another `---` and the solution, drawn with
+/// `#` for walls.
+pub fn parse_to_level(s: &str) -> Result<Level, ParseError> {
+  let (rest, (title, comment)) = header(s).map_err(|e| {
+    let at = nom_error_pos(s, e);
+    // Giving up anywhere on the first line means it never ended
+    if !s[..at].contains('\n') {
+      ParseError::new(0..s.len(), ParseErrorKind::MissingTitle)
+    } else {
+      ParseError::new(at..s.len(), ParseErrorKind::MissingSeparator)
+    }
+  })?;
+  let (puzzle, solution) = puzzle(s, s.offset(rest))?;
+  let mut level = Level::new(puzzle, title, comment);
+  level.set_solution(solution);
   Ok(level)
 }
 
-fn level(s: &str) -> IResult<&str, Level, VerboseError<&str>> {
-  let (s, title) = header(s)?;
-  let (s, puzzle) = puzzle(s)?;
-  let (s, _) = eof(s)?;
-  Ok((s, Level::new(puzzle, title)))
-}
-
-/// Returns the title
-fn header(s: &str) -> IResult<&str, String, VerboseError<&str>> {
+/// Returns the title and comment
+fn header(s: &str) -> IResult<&str, (String, String), VerboseError<&str>> {
   let (s, title) = terminated(not_line_ending, line_ending)(s)?;
 
-  let (s, _comment) =
+  let (s, comment) =
     discard_ws_after(terminated(take_until("---"), take(3usize)))(s)?;
-  Ok((s, title.to_string()))
+  Ok((
+    s,
+    (title.to_string(), trim_blank_lines(comment).to_string()),
+  ))
+}
+
+/// Drop blank lines from both ends, keeping the indentation of the first line
+/// that isn't blank.
+fn trim_blank_lines(s: &str) -> &str {
+  let start = s
+    .split_inclusive('\n')
+    .take_while(|line| line.trim().is_empty())
+    .map(str::len)
+    .sum();
+  s[start..].trim_end()
 }
 
-fn puzzle(s: &str) -> IResult<&str, Puzzle, VerboseError<&str>> {
-  let (s, _corner) = char(' ')(s)?;
-  let (s, top_hints) =
-    discard_ws_after(take_while(|c: char| c.is_ascii_digit()))(s)?;
-  let (s, puzzle_lines) = many0(|s| puzzle_line(s, top_hints.len()))(s)?;
-  let (s, _trail) = multispace0(s)?;
+/// Parse the hints, grid and solution, which start `start` bytes into `src`.
+///
+/// This goes line by line instead of through nom so we can say exactly which
+/// row is wrong.
+fn puzzle(
+  src: &str,
+  start: usize,
+) -> Result<(Puzzle, Option<Walls>), ParseError> {
+  let mut lines = lines_with_offsets(src, start);
+
+  let (top_start, top_line) = match lines.next() {
+    Some(it) => it,
+    None => {
+      return Err(ParseError::new(
+        src.len()..src.len(),
+        ParseErrorKind::MissingHints,
+      ))
+    }
+  };
+  let top_hints = match top_line.strip_prefix(' ') {
+    Some(hints) => {
+      let (rest, top_hints) =
+        many0(hint)(hints).map_err(|e| bad_hint(src, nom_error_pos(src, e)))?;
+      if !rest.trim().is_empty() {
+        return Err(bad_hint(src, src.offset(rest)));
+      }
+      top_hints
+    }
+    None => {
+      return Err(ParseError::new(
+        top_start..top_start + top_line.len(),
+        ParseErrorKind::MissingHintCorner,
+      ))
+    }
+  };
+  let width = top_hints.len();
+
+  let mut rows = Vec::new();
+  let mut solution_start = None;
+  let mut lines = lines.peekable();
+  while let Some((row_start, line)) = lines.next() {
+    if line.trim().is_empty() || line.trim() == "---" {
+      // Past the grid, only blank lines and the solution are allowed.
+      let separator = if line.trim() == "---" {
+        Some((row_start, line))
+      } else {
+        lines.find(|(_, l)| !l.trim().is_empty())
+      };
+      match separator {
+        Some((_, sep)) if sep.trim() == "---" => {
+          solution_start = Some(lines.peek().map_or(src.len(), |(o, _)| *o));
+        }
+        Some((extra_start, extra)) => {
+          return Err(ParseError::new(
+            extra_start..extra_start + extra.trim_end().len(),
+            ParseErrorKind::TrailingText,
+          ));
+        }
+        None => {}
+      }
+      break;
+    }
+
+    // The hint starts the line, and nom would point inside its brackets
+    let (tiles_str, hint) = hint(line).map_err(|_| bad_hint(src, row_start))?;
+    let tiles_str = tiles_str.trim_end();
+    let tiles_start = src.offset(tiles_str);
+    let mut tiles = Vec::new();
+    for (i, ch) in tiles_str.char_indices() {
+      let tile = match ch {
+        '@' => Some(Tile::Monster),
+        '$' => Some(Tile::TreasureChest),
+        '.' => None,
+        _ => {
+          let at = tiles_start + i;
+          return Err(ParseError::new(
+            at..at + ch.len_utf8(),
+            ParseErrorKind::UnknownTile(ch),
+          ));
+        }
+      };
+      tiles.push(tile);
+    }
+    if tiles.len() != width {
+      return Err(ParseError::new(
+        row_start..tiles_start + tiles_str.len(),
+        ParseErrorKind::WrongRowLength {
+          row: rows.len(),
+          found: tiles.len(),
+          expected: width,
+        },
+      ));
+    }
+    rows.push(PuzzleLine { hint, tiles });
+  }
 
   // and convert
-  let top_hints = top_hints
-    .chars()
-    .map(|c| c.to_digit(10).unwrap() as u8)
-    .collect::<Vec<_>>();
-  let mut grid = Grid::new(top_hints.len() as u32, puzzle_lines.len() as u32);
+  let mut grid = Grid::new(width as u32, rows.len() as u32);
   let mut side_hints = Vec::new();
-  for (y, pl) in puzzle_lines.into_iter().enumerate() {
+  for (y, pl) in rows.into_iter().enumerate() {
     side_hints.push(pl.hint);
     for (x, tile) in pl.tiles.into_iter().enumerate() {
       if let Some(tile) = tile {
@@ -61,27 +169,88 @@ fn puzzle(s: &str) -> IResult<&str, Puzzle, VerboseError<&str>> {
     }
   }
 
-  Ok((s, Puzzle::new(grid, top_hints, side_hints)))
+  let puzzle = Puzzle::new(grid, top_hints, side_hints);
+  let solution = match solution_start {
+    Some(start) => Some(solution(src, start, &puzzle)?),
+    None => None,
+  };
+  Ok((puzzle, solution))
 }
 
-fn puzzle_line(
-  s: &str,
-  len: usize,
-) -> IResult<&str, PuzzleLine, VerboseError<&str>> {
-  let (s, hint) = map(satisfy(|c| c.is_ascii_digit()), |c| {
-    c.to_digit(10).unwrap() as u8
-  })(s)?;
-  let (s, tiles) = discard_ws_after(count(a_tile, len))(s)?;
-  Ok((s, PuzzleLine { hint, tiles }))
+/// Parse the optional solution after the grid, starting `start` bytes into
+/// `src`. `#` is a wall; `.`, `@` and `$` are open.
+fn solution(
+  src: &str,
+  start: usize,
+  puzzle: &Puzzle,
+) -> Result<Walls, ParseError> {
+  let width = puzzle.width() as usize;
+  let mut walls = Vec::new();
+  let mut rows = 0;
+  let mut lines = lines_with_offsets(src, start);
+  for (row_start, line) in lines.by_ref() {
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    let mut found = 0;
+    for (i, ch) in line.char_indices() {
+      let wall = match ch {
+        '#' => true,
+        '.' | '@' | '$' => false,
+        _ => {
+          let at = row_start + i;
+          return Err(ParseError::new(
+            at..at + ch.len_utf8(),
+            ParseErrorKind::UnknownTile(ch),
+          ));
+        }
+      };
+      walls.push(wall);
+      found += 1;
+    }
+    if found != width {
+      return Err(ParseError::new(
+        row_start..row_start + line.len(),
+        ParseErrorKind::WrongRowLength {
+          row: rows,
+          found,
+          expected: width,
+        },
+      ));
+    }
+    rows += 1;
+  }
+
+  if let Some((extra_start, extra)) = lines.find(|(_, l)| !l.trim().is_empty())
+  {
+    return Err(ParseError::new(
+      extra_start..extra_start + extra.trim_end().len(),
+      ParseErrorKind::TrailingText,
+    ));
+  }
+  if rows != puzzle.height() as usize {
+    return Err(ParseError::new(
+      start..src.len(),
+      ParseErrorKind::WrongSolutionHeight {
+        found: rows,
+        expected: puzzle.height() as usize,
+      },
+    ));
+  }
+
+  Ok(Walls::new(puzzle.width(), puzzle.height(), walls))
 }
 
-fn a_tile(s: &str) -> IResult<&str, Option<Tile>, VerboseError<&str>> {
+/// Either a single digit, or any number in brackets like `[12]`.
+fn hint(s: &str) -> IResult<&str, u8, VerboseError<&str>> {
   context(
-    "tile",
+    "hint",
     alt((
-      value(Some(Tile::Monster), char('@')),
-      value(Some(Tile::TreasureChest), char('$')),
-      value(None, char('.')),
+      map(satisfy(|c| c.is_ascii_digit()), |c| {
+        c.to_digit(10).unwrap() as u8
+      }),
+      delimited(char('['), map_res(digit1, str::parse), char(']')),
     )),
   )(s)
 }
@@ -96,7 +265,281 @@ where
   terminated(inner, tuple((space0, opt(line_ending))))
 }
 
+/// Lines of `src` from `start` on, paired with their byte offsets,
+/// without their line endings.
+fn lines_with_offsets(
+  src: &str,
+  start: usize,
+) -> impl Iterator<Item = (usize, &str)> {
+  src[start..].split_inclusive('\n').map(move |line| {
+    let offset = src.offset(line);
+    let line = line.trim_end_matches('\n').trim_end_matches('\r');
+    (offset, line)
+  })
+}
+
+/// Where in `src` nom gave up.
+fn nom_error_pos(src: &str, err: nom::Err<VerboseError<&str>>) -> usize {
+  match err {
+    nom::Err::Error(e) | nom::Err::Failure(e) => {
+      e.errors.first().map(|(at, _)| src.offset(at)).unwrap_or(0)
+    }
+    nom::Err::Incomplete(_) => src.len(),
+  }
+}
+
+/// Span the whole hint-ish token at `at`, so `[12` gets underlined fully.
+fn bad_hint(src: &str, at: usize) -> ParseError {
+  let token = src[at..]
+    .find(|c: char| c.is_whitespace() || "@$.".contains(c))
+    .unwrap_or(src.len() - at);
+  let len = match src[at..at + token].find(']') {
+    Some(close) => close + 1,
+    None => token,
+  }
+  .max(1)
+  .min(src.len() - at);
+  ParseError::new(at..at + len, ParseErrorKind::BadHint)
+}
+
 struct PuzzleLine {
   hint: u8,
   tiles: Vec<Option<Tile>>,
 }
+
+/// Something wrong with a `.ttc` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  /// Byte range into the source the problem is at.
+  pub span: Range<usize>,
+  pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+  pub fn new(span: Range<usize>, kind: ParseErrorKind) -> Self {
+    Self { span, kind }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+  /// The file doesn't even have a complete first line.
+  MissingTitle,
+  /// There's no `---` after the comments.
+  MissingSeparator,
+  /// Nothing comes after the `---`.
+  MissingHints,
+  /// The hint row has to start with a space, to line up with the grid.
+  MissingHintCorner,
+  /// Expected a digit or a bracketed number.
+  BadHint,
+  UnknownTile(char),
+  /// Rows are zero-indexed here, but get printed one-indexed.
+  WrongRowLength {
+    row: usize,
+    found: usize,
+    expected: usize,
+  },
+  /// Something non-blank after the blank lines ending the grid.
+  TrailingText,
+  /// The solution has a different number of rows than the grid.
+  WrongSolutionHeight {
+    found: usize,
+    expected: usize,
+  },
+}
+
+impl fmt::Display for ParseErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ParseErrorKind::MissingTitle => {
+        write!(f, "expected a title on the first line")
+      }
+      ParseErrorKind::MissingSeparator => {
+        write!(f, "expected `---` after the comments")
+      }
+      ParseErrorKind::MissingHints => {
+        write!(f, "expected a row of hints after the `---`")
+      }
+      ParseErrorKind::MissingHintCorner => {
+        write!(f, "the hint row must start with a space")
+      }
+      ParseErrorKind::BadHint => {
+        write!(f, "expected a hint: a digit, or a number like `[12]`")
+      }
+      ParseErrorKind::UnknownTile(ch) => {
+        write!(f, "unknown tile character {:?}", ch)
+      }
+      ParseErrorKind::WrongRowLength {
+        row,
+        found,
+        expected,
+      } => write!(
+        f,
+        "row {} has {} tiles, expected {}",
+        row + 1,
+        found,
+        expected
+      ),
+      ParseErrorKind::TrailingText => {
+        write!(f, "unexpected text after the grid")
+      }
+      ParseErrorKind::WrongSolutionHeight { found, expected } => {
+        write!(f, "the solution has {} rows, expected {}", found, expected)
+      }
+    }
+  }
+}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::testing::BRIGHTLEAF;
+
+  /// A level with `top` as the hint row and `row` as its only grid row.
+  fn source(top: &str, row: &str) -> String {
+    format!("Test\n---\n {}\n{}\n", top, row)
+  }
+
+  fn err(src: &str) -> (ParseErrorKind, &str) {
+    let e = parse_to_level(src).unwrap_err();
+    (e.kind, &src[e.span])
+  }
+
+  #[test]
+  fn bracketed_hints() {
+    let level = parse_to_level(&source("1[12]0[255]", "[10]....")).unwrap();
+    assert_eq!(level.puzzle().top_hints(), &[1, 12, 0, 255]);
+    assert_eq!(level.puzzle().side_hints(), &[10]);
+    // Brackets around small numbers are fine too
+    let level = parse_to_level(&source("[3]", "[07].")).unwrap();
+    assert_eq!(level.puzzle().top_hints(), &[3]);
+    assert_eq!(level.puzzle().side_hints(), &[7]);
+  }
+
+  #[test]
+  fn bad_bracketed_hints() {
+    assert_eq!(
+      err(&source("1[256]", "0..")),
+      (ParseErrorKind::BadHint, "[256]")
+    );
+    assert_eq!(
+      err(&source("1[12", "0..")),
+      (ParseErrorKind::BadHint, "[12")
+    );
+    assert_eq!(
+      err(&source("1[]2", "0...")),
+      (ParseErrorKind::BadHint, "[]")
+    );
+    assert_eq!(
+      err(&source("12", "[256]..")),
+      (ParseErrorKind::BadHint, "[256]")
+    );
+    assert_eq!(err(&source("12", "[3..")), (ParseErrorKind::BadHint, "[3"));
+    assert_eq!(err(&source("12", "[]..")), (ParseErrorKind::BadHint, "[]"));
+  }
+
+  #[test]
+  fn parses_brightleaf() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    assert_eq!(level.title(), "Brightleaf Iron Mine");
+    assert!(level.comment().starts_with("The first puzzle"));
+    assert_eq!(level.puzzle().width(), 8);
+    assert_eq!(level.puzzle().height(), 8);
+    assert_eq!(
+      level.puzzle().get_tile(Coord::new(1, 5)),
+      Some(Tile::TreasureChest)
+    );
+    assert!(level.solution().is_some());
+  }
+
+  #[test]
+  fn missing_title() {
+    assert_eq!(err(""), (ParseErrorKind::MissingTitle, ""));
+    assert_eq!(err("Title"), (ParseErrorKind::MissingTitle, "Title"));
+  }
+
+  #[test]
+  fn missing_separator() {
+    assert_eq!(
+      err("Title\nno dashes here\n"),
+      (ParseErrorKind::MissingSeparator, "no dashes here\n")
+    );
+  }
+
+  #[test]
+  fn missing_hints() {
+    assert_eq!(err("Title\n---\n"), (ParseErrorKind::MissingHints, ""));
+  }
+
+  #[test]
+  fn missing_hint_corner() {
+    assert_eq!(
+      err("Title\n---\n12\n1..\n"),
+      (ParseErrorKind::MissingHintCorner, "12")
+    );
+  }
+
+  #[test]
+  fn bad_hint() {
+    assert_eq!(err(&source("1x", "1..")), (ParseErrorKind::BadHint, "x"));
+    assert_eq!(err(&source("12", "x..")), (ParseErrorKind::BadHint, "x"));
+  }
+
+  #[test]
+  fn unknown_tile() {
+    assert_eq!(
+      err(&source("12", "1.%")),
+      (ParseErrorKind::UnknownTile('%'), "%")
+    );
+    assert_eq!(
+      err("Title\n---\n 1\n1.\n---\n%\n"),
+      (ParseErrorKind::UnknownTile('%'), "%")
+    );
+  }
+
+  #[test]
+  fn wrong_row_length() {
+    let expected = ParseErrorKind::WrongRowLength {
+      row: 0,
+      found: 3,
+      expected: 2,
+    };
+    assert_eq!(err(&source("12", "1...")), (expected.clone(), "1..."));
+    assert_eq!(err("Title\n---\n 12\n1..\n---\n##.\n"), (expected, "##."));
+  }
+
+  #[test]
+  fn trailing_text() {
+    assert_eq!(
+      err("Title\n---\n 1\n1.\n\nmore \n"),
+      (ParseErrorKind::TrailingText, "more")
+    );
+    assert_eq!(
+      err("Title\n---\n 1\n1.\n---\n#\n\nmore\n"),
+      (ParseErrorKind::TrailingText, "more")
+    );
+  }
+
+  #[test]
+  fn wrong_solution_height() {
+    assert_eq!(
+      err("Title\n---\n 1\n1.\n0.\n---\n#\n"),
+      (
+        ParseErrorKind::WrongSolutionHeight {
+          found: 1,
+          expected: 2
+        },
+        "#\n"
+      )
+    );
+  }
+}
@@ -0,0 +1,161 @@
+use std::fmt::{self, Display, Write};
+
+use aglet::Coord;
+
+use crate::{Level, Solution, Tile};
+
+impl Level {
+  /// Write this level out in the `.ttc` format `parse_to_level` reads.
+  ///
+  /// The format has no way to quote text, so line breaks in the title become
+  /// spaces and any `---` in the comment gets a space put in it.
+  pub fn to_ttc_string(&self) -> String {
+    self.to_string()
+  }
+}
+
+impl Display for Level {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{}", self.title.replace(['\r', '\n'], " "))?;
+    writeln!(f)?;
+    if !self.comment.is_empty() {
+      writeln!(f, "{}", escape_comment(&self.comment))?;
+      writeln!(f)?;
+    }
+    writeln!(f, "---")?;
+
+    let puzzle = &self.puzzle;
+    f.write_char(' ')?;
+    for &hint in puzzle.top_hints() {
+      write_hint(f, hint)?;
+    }
+    writeln!(f)?;
+
+    for (y, &hint) in puzzle.side_hints().iter().enumerate() {
+      write_hint(f, hint)?;
+      for x in 0..puzzle.width() {
+        let ch = match puzzle.get_tile(Coord::new(x, y as u32)) {
+          Some(Tile::Monster) => '@',
+          Some(Tile::TreasureChest) => '$',
+          None => '.',
+        };
+        f.write_char(ch)?;
+      }
+      writeln!(f)?;
+    }
+
+    if let Some(solution) = &self.solution {
+      writeln!(f, "---")?;
+      for y in 0..puzzle.height() {
+        for x in 0..puzzle.width() {
+          let coord = Coord::new(x, y);
+          let ch = match puzzle.get_tile(coord) {
+            _ if solution.is_wall(coord) => '#',
+            Some(Tile::Monster) => '@',
+            Some(Tile::TreasureChest) => '$',
+            None => '.',
+          };
+          f.write_char(ch)?;
+        }
+        writeln!(f)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The first `---` ends the comment, so break up every run of dashes.
+fn escape_comment(comment: &str) -> String {
+  let mut out = comment.to_string();
+  while out.contains("---") {
+    out = out.replace("---", "- --");
+  }
+  out
+}
+
+/// Hints past 9 don't fit in one digit, so they get brackets.
+fn write_hint(f: &mut fmt::Formatter<'_>, hint: u8) -> fmt::Result {
+  if hint < 10 {
+    write!(f, "{}", hint)
+  } else {
+    write!(f, "[{}]", hint)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{parse_to_level, Level};
+
+  const LEVEL: &str = "Round Trip
+
+  An indented first line,
+and some more comment.
+
+---
+ [10]0[11]
+1$..
+[12].@.
+---
+$.#
+#@#
+";
+
+  fn assert_same(a: &Level, b: &Level) {
+    let (pa, pb) = (a.puzzle(), b.puzzle());
+    assert_eq!(a.title(), b.title());
+    assert_eq!(a.comment(), b.comment());
+    assert_eq!(pa.top_hints(), pb.top_hints());
+    assert_eq!(pa.side_hints(), pb.side_hints());
+    assert_eq!((pa.width(), pa.height()), (pb.width(), pb.height()));
+    for y in 0..pa.height() {
+      for x in 0..pa.width() {
+        let coord = aglet::Coord::new(x, y);
+        assert_eq!(pa.get_tile(coord), pb.get_tile(coord), "at {}", coord);
+      }
+    }
+    assert_eq!(a.solution(), b.solution());
+  }
+
+  #[test]
+  fn round_trips() {
+    let level = parse_to_level(LEVEL).unwrap();
+    assert_eq!(
+      level.comment(),
+      "  An indented first line,\nand some more comment."
+    );
+    assert_eq!(level.puzzle().top_hints(), &[10, 0, 11]);
+    assert!(level.solution().is_some());
+
+    let written = level.to_ttc_string();
+    assert_eq!(written, LEVEL);
+    let again = parse_to_level(&written).unwrap();
+    assert_same(&level, &again);
+    assert_eq!(again.to_ttc_string(), written);
+  }
+
+  #[test]
+  fn escapes_what_would_break_parsing() {
+    let level = parse_to_level(LEVEL).unwrap();
+    let level = Level::new(
+      level.puzzle().clone(),
+      "Two\nLines".to_string(),
+      "Before\n---\nafter, and a ---- here".to_string(),
+    );
+    let written = level.to_ttc_string();
+    let again = parse_to_level(&written).unwrap();
+    assert_eq!(again.title(), "Two Lines");
+    assert_eq!(again.comment(), "Before\n- --\nafter, and a - - -- here");
+    assert_eq!(again.puzzle().top_hints(), level.puzzle().top_hints());
+    assert_eq!(again.puzzle().side_hints(), level.puzzle().side_hints());
+    assert_eq!(again.to_ttc_string(), written);
+  }
+
+  #[test]
+  fn round_trips_without_extras() {
+    let level = parse_to_level("Bare\n---\n 01\n0..\n1.@\n").unwrap();
+    assert_eq!(level.comment(), "");
+    assert_eq!(level.solution(), None);
+    let again = parse_to_level(&level.to_ttc_string()).unwrap();
+    assert_same(&level, &again);
+  }
+}
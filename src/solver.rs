@@ -0,0 +1,743 @@
+//! Constraint-propagation solver.
+//!
+//! The solver keeps a three-state board (unknown, wall, open) and repeatedly
+//! applies the deductions a human would make: hint saturation, dead end logic,
+//! treasure room placement and connectivity. Only when none of those make
+//! progress does it guess a cell and backtrack.
+
+use aglet::Coord;
+
+use crate::{
+  bitboard::{WallGrid, MAX_WIDTH},
+  board::{Board, Cell},
+  checker::room_border,
+  difficulty::Technique,
+  hint::{Hint, Line, Reason},
+  Puzzle, Solution, Tile,
+};
+
+impl Puzzle {
+  /// Find a wall layout satisfying every rule and hint.
+  ///
+  /// Returns `None` if the puzzle has no solution.
+  pub fn solve(&self) -> Option<Walls> {
+    let mut found = None;
+    search(State::new(self), &mut |walls| {
+      found = Some(walls);
+      false
+    });
+    found
+  }
+
+  /// Find up to `limit` distinct solutions.
+  pub fn solutions(&self, limit: usize) -> Vec<Walls> {
+    let mut found = Vec::new();
+    if limit == 0 {
+      return found;
+    }
+    search(State::new(self), &mut |walls| {
+      found.push(walls);
+      found.len() < limit
+    });
+    found
+  }
+
+  /// Whether the board is still on track, as far as the solver can tell
+  /// without guessing.
+  ///
+  /// `true` doesn't mean the board can be finished; `false` means it can't.
+  pub fn is_consistent(&self, board: &Board) -> bool {
+    match State::from_board(self, board) {
+      Some(mut state) => state.propagate().is_ok(),
+      None => false,
+    }
+  }
+
+  /// Check whether this puzzle has exactly one solution.
+  pub fn uniqueness(&self) -> Uniqueness {
+    let mut solutions = self.solutions(2).into_iter();
+    match (solutions.next(), solutions.next()) {
+      (None, _) => Uniqueness::NoSolution,
+      (Some(it), None) => Uniqueness::Unique(it),
+      (Some(a), Some(b)) => Uniqueness::Multiple(a, b),
+    }
+  }
+}
+
+/// How many solutions a puzzle has.
+#[derive(Debug, Clone)]
+pub enum Uniqueness {
+  NoSolution,
+  Unique(Walls),
+  /// Two differing solutions, as witnesses.
+  Multiple(Walls, Walls),
+}
+
+/// A fully decided wall layout, as produced by the solver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Walls {
+  width: u32,
+  height: u32,
+  walls: Vec<bool>,
+}
+
+impl Walls {
+  /// `walls` goes row by row and must have `width * height` entries.
+  pub fn new(width: u32, height: u32, walls: Vec<bool>) -> Self {
+    assert_eq!(walls.len(), (width * height) as usize);
+    Self {
+      width,
+      height,
+      walls,
+    }
+  }
+
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+}
+
+impl Solution for Walls {
+  fn is_wall(&self, coord: Coord) -> bool {
+    coord.x < self.width
+      && coord.y < self.height
+      && self.walls[(coord.y * self.width + coord.x) as usize]
+  }
+}
+
+/// The board was driven into a state no solution can come from.
+#[derive(Debug, Clone, Copy)]
+struct Contradiction;
+
+/// A cell some rule says has to be a certain way.
+type Forced = Vec<(Coord, Cell, Reason)>;
+
+type Rule<'p> = fn(&State<'p>, &mut Forced) -> Result<(), Contradiction>;
+
+#[derive(Clone)]
+struct State<'p> {
+  puzzle: &'p Puzzle,
+  cells: Vec<Cell>,
+}
+
+impl<'p> State<'p> {
+  fn new(puzzle: &'p Puzzle) -> Self {
+    let mut cells =
+      vec![Cell::Unknown; (puzzle.width() * puzzle.height()) as usize];
+    for y in 0..puzzle.height() {
+      for x in 0..puzzle.width() {
+        if puzzle.get_tile(Coord::new(x, y)).is_some() {
+          cells[(y * puzzle.width() + x) as usize] = Cell::Open;
+        }
+      }
+    }
+    Self { puzzle, cells }
+  }
+
+  /// Start from what's filled in on the board, or `None` if that already
+  /// clashes with the tiles.
+  fn from_board(puzzle: &'p Puzzle, board: &Board) -> Option<Self> {
+    let mut state = State::new(puzzle);
+    for coord in board.coords() {
+      let cell = board.get(coord);
+      if cell != Cell::Unknown {
+        state.set(coord, cell).ok()?;
+      }
+    }
+    Some(state)
+  }
+
+  fn width(&self) -> u32 {
+    self.puzzle.width()
+  }
+
+  fn height(&self) -> u32 {
+    self.puzzle.height()
+  }
+
+  /// Out-of-bounds cells count as walls.
+  fn get(&self, x: i32, y: i32) -> Cell {
+    if x < 0 || y < 0 || x as u32 >= self.width() || y as u32 >= self.height() {
+      Cell::Wall
+    } else {
+      self.cells[(y as u32 * self.width() + x as u32) as usize]
+    }
+  }
+
+  /// Returns whether anything changed.
+  fn set(&mut self, coord: Coord, cell: Cell) -> Result<bool, Contradiction> {
+    let idx = (coord.y * self.width() + coord.x) as usize;
+    match (self.cells[idx], cell) {
+      (Cell::Unknown, _) => {
+        self.cells[idx] = cell;
+        Ok(true)
+      }
+      (old, new) if old == new => Ok(false),
+      _ => Err(Contradiction),
+    }
+  }
+
+  fn coords(&self) -> impl Iterator<Item = Coord> {
+    let (w, h) = (self.width(), self.height());
+    (0..h).flat_map(move |y| (0..w).map(move |x| Coord::new(x, y)))
+  }
+
+  fn tile_count(&self) -> usize {
+    self
+      .coords()
+      .filter(|&c| self.puzzle.get_tile(c).is_some())
+      .count()
+  }
+
+  /// Run every deduction until none of them make progress.
+  fn propagate(&mut self) -> Result<(), Contradiction> {
+    loop {
+      let mut forced = Vec::new();
+      self.rule_hints(&mut forced)?;
+      self.rule_dead_ends(&mut forced)?;
+      self.rule_treasure_rooms(&mut forced)?;
+      self.rule_connectivity(&mut forced)?;
+
+      let mut changed = false;
+      for (coord, cell, _) in forced {
+        changed |= self.set(coord, cell)?;
+      }
+      if !changed {
+        return Ok(());
+      }
+    }
+  }
+
+  /// A line that already has all its walls is open everywhere else;
+  /// a line that needs every unknown cell to be a wall gets them.
+  fn rule_hints(&self, forced: &mut Forced) -> Result<(), Contradiction> {
+    let rows = self.puzzle.side_hints().iter().enumerate().map(|(y, &h)| {
+      let cells = (0..self.width())
+        .map(|x| Coord::new(x, y as u32))
+        .collect::<Vec<_>>();
+      (cells, h, Line::Row(y as u32))
+    });
+    let cols = self.puzzle.top_hints().iter().enumerate().map(|(x, &h)| {
+      let cells = (0..self.height())
+        .map(|y| Coord::new(x as u32, y))
+        .collect::<Vec<_>>();
+      (cells, h, Line::Column(x as u32))
+    });
+
+    for (line, hint, which) in rows.chain(cols) {
+      let hint = hint as usize;
+      let walls = line
+        .iter()
+        .filter(|c| self.get(c.x as _, c.y as _) == Cell::Wall)
+        .count();
+      let unknowns = line
+        .iter()
+        .filter(|c| self.get(c.x as _, c.y as _) == Cell::Unknown)
+        .copied()
+        .collect::<Vec<_>>();
+      if walls > hint || walls + unknowns.len() < hint {
+        return Err(Contradiction);
+      }
+      if unknowns.is_empty() {
+        continue;
+      }
+      if walls == hint {
+        let reason = Reason::LineFull(which);
+        forced.extend(unknowns.iter().map(|&c| (c, Cell::Open, reason)));
+      } else if walls + unknowns.len() == hint {
+        let reason = Reason::LineNeedsWalls(which);
+        forced.extend(unknowns.iter().map(|&c| (c, Cell::Wall, reason)));
+      }
+    }
+    Ok(())
+  }
+
+  /// Monsters sit in dead ends with exactly one way out; every other open
+  /// cell needs at least two.
+  fn rule_dead_ends(&self, forced: &mut Forced) -> Result<(), Contradiction> {
+    // A lone monster with nothing else on the board may be walled in entirely.
+    let must_connect = self.tile_count() > 1;
+
+    for coord in self.coords() {
+      let (x, y) = (coord.x as i32, coord.y as i32);
+      let neighbors = [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)];
+      let open = neighbors
+        .iter()
+        .filter(|&&(nx, ny)| self.get(nx, ny) == Cell::Open)
+        .count();
+      let unknowns = neighbors
+        .iter()
+        .filter(|&&(nx, ny)| self.get(nx, ny) == Cell::Unknown)
+        .map(|&(nx, ny)| Coord::new(nx as u32, ny as u32))
+        .collect::<Vec<_>>();
+      let free = open + unknowns.len();
+
+      let tile = self.puzzle.get_tile(coord);
+      match (self.get(x, y), tile) {
+        (Cell::Open, Some(Tile::Monster)) => {
+          if open >= 2 || (free == 0 && must_connect) {
+            return Err(Contradiction);
+          }
+          if open == 1 {
+            let reason = Reason::MonsterHasExit(coord);
+            forced.extend(unknowns.iter().map(|&c| (c, Cell::Wall, reason)));
+          } else if free == 1 && must_connect {
+            forced.push((
+              unknowns[0],
+              Cell::Open,
+              Reason::MonsterNeedsExit(coord),
+            ));
+          }
+        }
+        (Cell::Open, _) => {
+          if free < 2 {
+            return Err(Contradiction);
+          }
+          if free == 2 {
+            let reason = Reason::CorridorNeedsExits(coord);
+            forced.extend(unknowns.iter().map(|&c| (c, Cell::Open, reason)));
+          }
+        }
+        // If this were open it would be a dead end with no monster.
+        (Cell::Unknown, None) if free <= 1 => {
+          forced.push((coord, Cell::Wall, Reason::WouldBeDeadEnd));
+        }
+        _ => {}
+      }
+    }
+    Ok(())
+  }
+
+  /// Each chest needs a 3x3 room with one entrance, and 2x2 open blocks may
+  /// only exist inside such a room.
+  fn rule_treasure_rooms(
+    &self,
+    forced: &mut Forced,
+  ) -> Result<(), Contradiction> {
+    let mut all_rooms = Vec::new();
+    for chest in self.coords() {
+      if self.puzzle.get_tile(chest) != Some(Tile::TreasureChest) {
+        continue;
+      }
+      let rooms = self.candidate_rooms(chest);
+      if rooms.is_empty() {
+        return Err(Contradiction);
+      }
+
+      // Cells every candidate room agrees on must be open.
+      for dy in 0..3 {
+        for dx in 0..3 {
+          let first = (rooms[0].0 + dx, rooms[0].1 + dy);
+          let shared = rooms.iter().all(|&(cx, cy)| {
+            (cx..cx + 3).contains(&first.0) && (cy..cy + 3).contains(&first.1)
+          });
+          if shared && self.get(first.0 as _, first.1 as _) == Cell::Unknown {
+            forced.push((
+              Coord::new(first.0, first.1),
+              Cell::Open,
+              Reason::RoomWindow(chest),
+            ));
+          }
+        }
+      }
+
+      if let [(cx, cy)] = rooms[..] {
+        let border = room_border(cx, cy);
+        let open = border
+          .iter()
+          .filter(|&&(bx, by)| self.get(bx, by) == Cell::Open)
+          .count();
+        let unknowns = border
+          .iter()
+          .filter(|&&(bx, by)| self.get(bx, by) == Cell::Unknown)
+          .map(|&(bx, by)| Coord::new(bx as u32, by as u32))
+          .collect::<Vec<_>>();
+        if open == 1 {
+          let reason = Reason::RoomHasEntrance(chest);
+          forced.extend(unknowns.iter().map(|&c| (c, Cell::Wall, reason)));
+        } else if open == 0 && unknowns.len() == 1 {
+          forced.push((
+            unknowns[0],
+            Cell::Open,
+            Reason::RoomNeedsEntrance(chest),
+          ));
+        }
+      }
+      all_rooms.extend(rooms);
+    }
+
+    for y in 0..self.height().saturating_sub(1) {
+      for x in 0..self.width().saturating_sub(1) {
+        let in_room = all_rooms.iter().any(|&(cx, cy)| {
+          cx <= x && x + 1 < cx + 3 && cy <= y && y + 1 < cy + 3
+        });
+        if in_room {
+          continue;
+        }
+        let block = [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+          .map(|(bx, by)| (Coord::new(bx, by), self.get(bx as _, by as _)));
+        let open = block.iter().filter(|(_, c)| *c == Cell::Open).count();
+        if open == 4 {
+          return Err(Contradiction);
+        }
+        if open == 3 {
+          if let Some(&(coord, _)) =
+            block.iter().find(|(_, c)| *c == Cell::Unknown)
+          {
+            forced.push((coord, Cell::Wall, Reason::LargeArea));
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Top-left corners of every 3x3 room around the chest that could still
+  /// work out.
+  fn candidate_rooms(&self, chest: Coord) -> Vec<(u32, u32)> {
+    let mut out = Vec::new();
+    for cy in chest.y.saturating_sub(2)..=chest.y {
+      for cx in chest.x.saturating_sub(2)..=chest.x {
+        if cx + 3 > self.width() || cy + 3 > self.height() {
+          continue;
+        }
+        let interior_ok = (cy..cy + 3).all(|y| {
+          (cx..cx + 3).all(|x| {
+            let tile = self.puzzle.get_tile(Coord::new(x, y));
+            self.get(x as _, y as _) != Cell::Wall
+              && tile != Some(Tile::Monster)
+              && (tile != Some(Tile::TreasureChest)
+                || Coord::new(x, y) == chest)
+          })
+        });
+        if !interior_ok {
+          continue;
+        }
+        let border = room_border(cx, cy);
+        let open = border
+          .iter()
+          .filter(|&&(bx, by)| self.get(bx, by) == Cell::Open)
+          .count();
+        let free = border
+          .iter()
+          .filter(|&&(bx, by)| self.get(bx, by) != Cell::Wall)
+          .count();
+        if open <= 1 && free >= 1 {
+          out.push((cx, cy));
+        }
+      }
+    }
+    out
+  }
+
+  /// All open cells must be reachable from each other through non-walls,
+  /// and unknown cells that can't reach them must be walls.
+  fn rule_connectivity(
+    &self,
+    forced: &mut Forced,
+  ) -> Result<(), Contradiction> {
+    let start = match self.coords().find(|c| self.get_coord(*c) == Cell::Open) {
+      Some(it) => it,
+      None => return Ok(()),
+    };
+
+    let mut seen = vec![false; self.cells.len()];
+    let mut todo = vec![start];
+    while let Some(here) = todo.pop() {
+      let idx = (here.y * self.width() + here.x) as usize;
+      if std::mem::replace(&mut seen[idx], true) {
+        continue;
+      }
+      for n in here.neighbors4() {
+        if n.x < self.width()
+          && n.y < self.height()
+          && self.get_coord(n) != Cell::Wall
+        {
+          todo.push(n);
+        }
+      }
+    }
+
+    for coord in self.coords() {
+      let idx = (coord.y * self.width() + coord.x) as usize;
+      if seen[idx] {
+        continue;
+      }
+      match self.get_coord(coord) {
+        Cell::Open => return Err(Contradiction),
+        Cell::Unknown => forced.push((coord, Cell::Wall, Reason::Unreachable)),
+        Cell::Wall => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn get_coord(&self, coord: Coord) -> Cell {
+    self.get(coord.x as _, coord.y as _)
+  }
+
+  /// Pick the unknown cell in the most constrained row or column.
+  fn pick_guess(&self) -> Option<Coord> {
+    let row_unknowns = (0..self.height())
+      .map(|y| {
+        (0..self.width())
+          .filter(|&x| self.get(x as _, y as _) == Cell::Unknown)
+          .count()
+      })
+      .collect::<Vec<_>>();
+    let col_unknowns = (0..self.width())
+      .map(|x| {
+        (0..self.height())
+          .filter(|&y| self.get(x as _, y as _) == Cell::Unknown)
+          .count()
+      })
+      .collect::<Vec<_>>();
+    self
+      .coords()
+      .filter(|&c| self.get_coord(c) == Cell::Unknown)
+      .min_by_key(|c| {
+        row_unknowns[c.y as usize].min(col_unknowns[c.x as usize])
+      })
+  }
+
+  fn to_walls(&self) -> Walls {
+    Walls {
+      width: self.width(),
+      height: self.height(),
+      walls: self.cells.iter().map(|&c| c == Cell::Wall).collect(),
+    }
+  }
+}
+
+/// The first thing the rules can deduce from the board, trying the simpler
+/// rules first.
+pub(crate) fn first_deduction<'p>(
+  puzzle: &'p Puzzle,
+  board: &Board,
+) -> Option<Hint> {
+  let state = State::from_board(puzzle, board)?;
+
+  let rules: [Rule<'p>; 4] = [
+    State::rule_hints,
+    State::rule_dead_ends,
+    State::rule_treasure_rooms,
+    State::rule_connectivity,
+  ];
+  for rule in rules {
+    let mut forced = Vec::new();
+    rule(&state, &mut forced).ok()?;
+    let found = forced
+      .into_iter()
+      .find(|&(coord, _, _)| state.get_coord(coord) == Cell::Unknown);
+    if let Some((coord, cell, reason)) = found {
+      return Some(Hint {
+        coord,
+        cell,
+        reason,
+      });
+    }
+  }
+  None
+}
+
+/// Solve like a person would, counting how many cells each technique (in
+/// [`Technique::ALL`] order) worked out. Returns `None` if there's no
+/// solution.
+pub(crate) fn grade(puzzle: &Puzzle) -> Option<[u32; 6]> {
+  let rules: [(Technique, Rule); 4] = [
+    (Technique::HintSaturation, State::rule_hints),
+    (Technique::DeadEnds, State::rule_dead_ends),
+    (Technique::TreasureRooms, State::rule_treasure_rooms),
+    (Technique::Connectivity, State::rule_connectivity),
+  ];
+  let mut counts = [0; 6];
+  let mut state = State::new(puzzle);
+
+  'progress: loop {
+    // Always go back to the easiest rule that does anything.
+    for (technique, rule) in rules {
+      let mut forced = Vec::new();
+      rule(&state, &mut forced).ok()?;
+      let mut changed = 0;
+      for (coord, cell, _) in forced {
+        if state.set(coord, cell).ok()? {
+          changed += 1;
+        }
+      }
+      if changed > 0 {
+        counts[technique as usize] += changed;
+        continue 'progress;
+      }
+    }
+
+    let unknowns = state
+      .coords()
+      .filter(|&c| state.get_coord(c) == Cell::Unknown)
+      .collect::<Vec<_>>();
+    if unknowns.is_empty() {
+      break;
+    }
+
+    // If one way leads straight to a contradiction, it's the other way.
+    for &coord in unknowns.iter() {
+      for (guess, other) in [(Cell::Wall, Cell::Open), (Cell::Open, Cell::Wall)]
+      {
+        let mut trial = state.clone();
+        if trial.set(coord, guess).is_err() || trial.propagate().is_err() {
+          state.set(coord, other).ok()?;
+          counts[Technique::Lookahead as usize] += 1;
+          continue 'progress;
+        }
+      }
+    }
+
+    // Out of ideas, so peek at the answer for one cell.
+    let coord = state.pick_guess()?;
+    let mut answer = None;
+    search(state.clone(), &mut |walls| {
+      answer = Some(walls);
+      false
+    });
+    let cell = if answer?.is_wall(coord) {
+      Cell::Wall
+    } else {
+      Cell::Open
+    };
+    state.set(coord, cell).ok()?;
+    counts[Technique::Backtracking as usize] += 1;
+  }
+
+  puzzle.check_solution(&state.to_walls(), false).ok()?;
+  Some(counts)
+}
+
+/// Depth-first search over guesses, calling `visit` with every solution.
+///
+/// `visit` returns whether to keep searching; so does this.
+fn search(mut state: State, visit: &mut dyn FnMut(Walls) -> bool) -> bool {
+  if state.propagate().is_err() {
+    return true;
+  }
+
+  let guess = match state.pick_guess() {
+    Some(it) => it,
+    None => {
+      let walls = state.to_walls();
+      let ok = if walls.width() <= MAX_WIDTH {
+        let grid =
+          WallGrid::from_solution(walls.width(), walls.height(), &walls);
+        state.puzzle.wall_grid_ok(&grid)
+      } else {
+        state.puzzle.check_solution(&walls, false).is_ok()
+      };
+      if ok {
+        return visit(walls);
+      }
+      return true;
+    }
+  };
+
+  for cell in [Cell::Wall, Cell::Open] {
+    let mut branch = state.clone();
+    if branch.set(guess, cell).is_ok() && !search(branch, visit) {
+      return false;
+    }
+  }
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use aglet::Grid;
+
+  use super::*;
+  use crate::{
+    parse_to_level,
+    testing::{board, BRIGHTLEAF},
+  };
+
+  #[test]
+  fn solves_brightleaf() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    let puzzle = level.puzzle();
+    let walls = puzzle.solve().unwrap();
+    assert!(puzzle.check_solution(&walls, false).is_ok());
+    assert_eq!(Some(&walls), level.solution());
+  }
+
+  #[test]
+  fn solves_drawn_boards() {
+    for rows in [
+      &["@.@", "#.#", "@.@"][..],
+      &["#####", "#...#", "#.$.#", "#...#", "##.##", "@...@"][..],
+    ] {
+      let (puzzle, _) = board(rows);
+      let walls = puzzle.solve().unwrap();
+      assert!(puzzle.check_solution(&walls, false).is_ok(), "{:?}", rows);
+    }
+  }
+
+  #[test]
+  fn no_solution() {
+    // One open cell is a dead end with no monster
+    let puzzle = Puzzle::new(Grid::new(1, 1), vec![0], vec![0]);
+    assert_eq!(puzzle.solve(), None);
+
+    // The hints ask for a wall on top of the chest
+    let (mut puzzle, _) = board(&["$.."]);
+    puzzle.top_hints = vec![1, 0, 0];
+    puzzle.side_hints = vec![1];
+    assert_eq!(puzzle.solve(), None);
+  }
+
+  /// Every row and column wants one wall, and the board can be flipped.
+  const AMBIGUOUS: &[&str] = &["...#", ".#..", "..#.", "#..."];
+
+  #[test]
+  fn brightleaf_is_unique() {
+    let level = parse_to_level(BRIGHTLEAF).unwrap();
+    match level.puzzle().uniqueness() {
+      Uniqueness::Unique(walls) => assert_eq!(Some(&walls), level.solution()),
+      other => panic!("expected a unique solution, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn no_solution_is_reported() {
+    let puzzle = Puzzle::new(Grid::new(1, 1), vec![0], vec![0]);
+    assert!(matches!(puzzle.uniqueness(), Uniqueness::NoSolution));
+    assert!(puzzle.solutions(5).is_empty());
+  }
+
+  #[test]
+  fn multiple_solutions() {
+    let (puzzle, _) = board(AMBIGUOUS);
+    match puzzle.uniqueness() {
+      Uniqueness::Multiple(a, b) => {
+        assert_ne!(a, b);
+        assert!(puzzle.check_solution(&a, false).is_ok());
+        assert!(puzzle.check_solution(&b, false).is_ok());
+      }
+      other => panic!("expected several solutions, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn solutions_stop_at_limit() {
+    let (puzzle, _) = board(AMBIGUOUS);
+    assert!(puzzle.solutions(0).is_empty());
+    assert_eq!(puzzle.solutions(1).len(), 1);
+
+    let all = puzzle.solutions(100);
+    assert!(all.len() > 1);
+    for (i, walls) in all.iter().enumerate() {
+      assert!(puzzle.check_solution(walls, false).is_ok());
+      assert!(!all[..i].contains(walls), "solution {} repeats", i);
+    }
+    assert_eq!(puzzle.solutions(all.len() - 1).len(), all.len() - 1);
+  }
+}
@@ -0,0 +1,138 @@
+//! Helpers shared by the tests.
+
+use aglet::{Coord, Grid};
+
+use crate::{solver::Walls, Puzzle, Solution, Tile};
+
+/// Build a puzzle and a solution from a picture.
+///
+/// `#` is a wall, `.` is open, `@` and `$` are a monster and chest, and `M`
+/// is a wall on top of a monster. Hints are read off the walls.
+pub fn board(rows: &[&str]) -> (Puzzle, Walls) {
+  let width = rows[0].len() as u32;
+  let height = rows.len() as u32;
+  let mut tiles = Grid::new(width, height);
+  let mut walls = Vec::new();
+  for (y, row) in rows.iter().enumerate() {
+    for (x, ch) in row.chars().enumerate() {
+      let coord = Coord::new(x as u32, y as u32);
+      match ch {
+        '@' | 'M' => {
+          tiles.insert(coord, Tile::Monster);
+        }
+        '$' => {
+          tiles.insert(coord, Tile::TreasureChest);
+        }
+        _ => {}
+      }
+      walls.push(ch == '#' || ch == 'M');
+    }
+  }
+  let walls = Walls::new(width, height, walls);
+  (puzzle_for(tiles, &walls), walls)
+}
+
+/// A puzzle with these tiles whose hints match `walls`.
+pub fn puzzle_for(tiles: Grid<Tile>, walls: &Walls) -> Puzzle {
+  let (width, height) = (walls.width(), walls.height());
+  let top_hints = (0..width)
+    .map(|x| {
+      (0..height)
+        .filter(|&y| walls.is_wall(Coord::new(x, y)))
+        .count() as u8
+    })
+    .collect();
+  let side_hints = (0..height)
+    .map(|y| {
+      (0..width)
+        .filter(|&x| walls.is_wall(Coord::new(x, y)))
+        .count() as u8
+    })
+    .collect();
+  Puzzle::new(tiles, top_hints, side_hints)
+}
+
+pub const BRIGHTLEAF: &str = include_str!("../puzzles/01-brightleaf.ttc");
+
+/// Cheap LCG so random tests are the same every run.
+pub struct Lcg(pub u64);
+
+impl Lcg {
+  pub fn below(&mut self, n: u32) -> u32 {
+    self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1);
+    ((self.0 >> 33) % n as u64) as u32
+  }
+
+  pub fn chance(&mut self, percent: u32) -> bool {
+    self.below(100) < percent
+  }
+}
+
+/// A random board whose hints match its walls, so checks get past the hints
+/// and down to the shape rules.
+///
+/// Most dead ends get monsters and about half the boards get a treasure room,
+/// so there's a fair mix of solved and broken boards.
+pub fn random_board(rng: &mut Lcg, width: u32, height: u32) -> (Puzzle, Walls) {
+  let mut walls = (0..width * height)
+    .map(|_| rng.chance(40))
+    .collect::<Vec<_>>();
+  let idx = |x: u32, y: u32| (y * width + x) as usize;
+  let mut tiles = Grid::new(width, height);
+
+  if width >= 3 && height >= 3 && rng.chance(50) {
+    let (cx, cy) = (rng.below(width - 2), rng.below(height - 2));
+    for y in cy..cy + 3 {
+      for x in cx..cx + 3 {
+        walls[idx(x, y)] = false;
+      }
+    }
+    let border = crate::checker::room_border(cx, cy)
+      .into_iter()
+      .filter(|&(x, y)| {
+        x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height
+      })
+      .collect::<Vec<_>>();
+    // A room filling the whole board has no border to put an entrance in
+    let entrance = rng.below(border.len().max(1) as u32) as usize;
+    for (i, &(x, y)) in border.iter().enumerate() {
+      walls[idx(x as u32, y as u32)] = i != entrance;
+    }
+    let chest = Coord::new(cx + rng.below(3), cy + rng.below(3));
+    tiles.insert(chest, Tile::TreasureChest);
+  }
+
+  let open = |walls: &[bool], x: i64, y: i64| {
+    x >= 0
+      && y >= 0
+      && x < width as i64
+      && y < height as i64
+      && !walls[idx(x as u32, y as u32)]
+  };
+  for y in 0..height {
+    for x in 0..width {
+      let coord = Coord::new(x, y);
+      let (ix, iy) = (x as i64, y as i64);
+      let exits = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .iter()
+        .filter(|(dx, dy)| open(&walls, ix + dx, iy + dy))
+        .count();
+      if open(&walls, ix, iy)
+        && exits <= 1
+        && tiles.get(coord).is_none()
+        && rng.chance(90)
+      {
+        tiles.insert(coord, Tile::Monster);
+      }
+    }
+  }
+
+  // And the odd mistake, like a wall on a tile
+  if rng.chance(20) {
+    let i = idx(rng.below(width), rng.below(height));
+    walls[i] = !walls[i];
+  }
+
+  let walls = Walls::new(width, height, walls);
+  (puzzle_for(tiles, &walls), walls)
+}
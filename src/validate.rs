@@ -0,0 +1,321 @@
+//! Sanity checks for levels that parsed fine but can't possibly be solved.
+
+use std::fmt;
+
+use aglet::Coord;
+
+use crate::{Level, Tile};
+
+impl Level {
+  /// Look for problems that make the level unsolvable (errors) or that are
+  /// probably mistakes (warnings).
+  ///
+  /// This doesn't run the solver, so an empty list doesn't mean the level
+  /// can be solved.
+  pub fn validate(&self) -> Vec<Diagnostic> {
+    let puzzle = self.puzzle();
+    let (width, height) = (puzzle.width(), puzzle.height());
+    let mut out = Vec::new();
+
+    if width == 0 || height == 0 {
+      out.push(Diagnostic::new(Severity::Error, Issue::EmptyBoard));
+      return out;
+    }
+
+    for (x, &hint) in puzzle.top_hints().iter().enumerate() {
+      if hint as u32 > height {
+        out.push(Diagnostic::new(
+          Severity::Error,
+          Issue::ColumnHintTooLarge {
+            column: x as u32,
+            hint,
+          },
+        ));
+      }
+    }
+    for (y, &hint) in puzzle.side_hints().iter().enumerate() {
+      if hint as u32 > width {
+        out.push(Diagnostic::new(
+          Severity::Error,
+          Issue::RowHintTooLarge {
+            row: y as u32,
+            hint,
+          },
+        ));
+      }
+    }
+
+    let top_sum = puzzle.top_hints().iter().map(|&h| h as u32).sum();
+    let side_sum = puzzle.side_hints().iter().map(|&h| h as u32).sum();
+    if top_sum != side_sum {
+      out.push(Diagnostic::new(
+        Severity::Error,
+        Issue::HintSumMismatch { top_sum, side_sum },
+      ));
+    }
+
+    let coords =
+      (0..height).flat_map(|y| (0..width).map(move |x| Coord::new(x, y)));
+    let mut any_tiles = false;
+    for coord in coords {
+      match puzzle.get_tile(coord) {
+        Some(Tile::TreasureChest) => {
+          any_tiles = true;
+          let has_room = (coord.y.saturating_sub(2)..=coord.y).any(|cy| {
+            (coord.x.saturating_sub(2)..=coord.x).any(|cx| {
+              cx + 3 <= width
+                && cy + 3 <= height
+                && (cy..cy + 3).all(|y| {
+                  (cx..cx + 3).all(|x| {
+                    let here = Coord::new(x, y);
+                    match puzzle.get_tile(here) {
+                      Some(Tile::Monster) => false,
+                      Some(Tile::TreasureChest) => here == coord,
+                      None => true,
+                    }
+                  })
+                })
+            })
+          });
+          if !has_room {
+            out.push(Diagnostic::new(
+              Severity::Error,
+              Issue::NoRoomForChest(coord),
+            ));
+          }
+        }
+        Some(Tile::Monster) => {
+          any_tiles = true;
+          // Two monsters next to each other can only be the whole dungeon.
+          for n in [
+            Coord::new(coord.x + 1, coord.y),
+            Coord::new(coord.x, coord.y + 1),
+          ] {
+            if puzzle.get_tile(n) == Some(Tile::Monster) {
+              out.push(Diagnostic::new(
+                Severity::Warning,
+                Issue::AdjacentMonsters(coord, n),
+              ));
+            }
+          }
+        }
+        None => {}
+      }
+    }
+    if !any_tiles {
+      out.push(Diagnostic::new(Severity::Warning, Issue::NoTiles));
+    }
+
+    out
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub issue: Issue,
+}
+
+impl Diagnostic {
+  pub fn new(severity: Severity, issue: Issue) -> Self {
+    Self { severity, issue }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  /// Probably a mistake, but might be solvable.
+  Warning,
+  /// Definitely can't be solved.
+  Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Issue {
+  /// There are no rows or no columns.
+  EmptyBoard,
+  /// Rows and columns are zero-indexed here, but get printed one-indexed.
+  RowHintTooLarge {
+    row: u32,
+    hint: u8,
+  },
+  ColumnHintTooLarge {
+    column: u32,
+    hint: u8,
+  },
+  /// Both sets of hints count every wall, so they have to agree.
+  HintSumMismatch {
+    top_sum: u32,
+    side_sum: u32,
+  },
+  /// No 3x3 area around the chest fits on the board without a monster or
+  /// another chest.
+  NoRoomForChest(Coord),
+  AdjacentMonsters(Coord, Coord),
+  /// No monsters or chests, so there's nothing to deduce from.
+  NoTiles,
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let severity = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{}: {}", severity, self.issue)
+  }
+}
+
+impl fmt::Display for Issue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Issue::EmptyBoard => write!(f, "the board has no rows or no columns"),
+      Issue::RowHintTooLarge { row, hint } => {
+        write!(
+          f,
+          "row {} wants {} walls, more than it has room for",
+          row + 1,
+          hint
+        )
+      }
+      Issue::ColumnHintTooLarge { column, hint } => write!(
+        f,
+        "column {} wants {} walls, more than it has room for",
+        column + 1,
+        hint
+      ),
+      Issue::HintSumMismatch { top_sum, side_sum } => write!(
+        f,
+        "the column hints add up to {} but the row hints add up to {}",
+        top_sum, side_sum
+      ),
+      Issue::NoRoomForChest(pos) => {
+        write!(
+          f,
+          "the chest at {} has no room for a 3x3 treasure room",
+          pos
+        )
+      }
+      Issue::AdjacentMonsters(a, b) => write!(
+        f,
+        "the monsters at {} and {} are next to each other, so they can only \
+         connect to each other",
+        a, b
+      ),
+      Issue::NoTiles => write!(f, "the board has no monsters or chests"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use aglet::Grid;
+
+  use super::*;
+  use crate::{testing::board, Puzzle};
+
+  /// The issues for a board drawn like in `testing::board`, with its hints
+  /// read off the walls unless they're given.
+  fn issues(rows: &[&str], hints: Option<(Vec<u8>, Vec<u8>)>) -> Vec<Issue> {
+    let (mut puzzle, _) = board(rows);
+    if let Some((top, side)) = hints {
+      puzzle.top_hints = top;
+      puzzle.side_hints = side;
+    }
+    let level = Level::new(puzzle, "Test".to_string(), String::new());
+    level.validate().into_iter().map(|d| d.issue).collect()
+  }
+
+  #[test]
+  fn good_level_is_fine() {
+    assert_eq!(issues(&["@.#", "#.@"], None), vec![]);
+  }
+
+  #[test]
+  fn empty_board() {
+    let puzzle = Puzzle::new(Grid::new(0, 0), vec![], vec![]);
+    let level = Level::new(puzzle, "Empty".to_string(), String::new());
+    assert_eq!(
+      level.validate(),
+      vec![Diagnostic::new(Severity::Error, Issue::EmptyBoard)]
+    );
+  }
+
+  #[test]
+  fn hints_too_large() {
+    assert_eq!(
+      issues(&["@.", ".@"], Some((vec![3, 0], vec![0, 3]))),
+      vec![
+        Issue::ColumnHintTooLarge { column: 0, hint: 3 },
+        Issue::RowHintTooLarge { row: 1, hint: 3 },
+      ]
+    );
+    assert_eq!(
+      Issue::RowHintTooLarge { row: 1, hint: 3 }.to_string(),
+      "row 2 wants 3 walls, more than it has room for"
+    );
+    assert_eq!(
+      Issue::ColumnHintTooLarge { column: 0, hint: 3 }.to_string(),
+      "column 1 wants 3 walls, more than it has room for"
+    );
+  }
+
+  #[test]
+  fn hint_sum_mismatch() {
+    assert_eq!(
+      issues(&["@.", ".@"], Some((vec![1, 1], vec![1, 0]))),
+      vec![Issue::HintSumMismatch {
+        top_sum: 2,
+        side_sum: 1
+      }]
+    );
+  }
+
+  #[test]
+  fn no_room_for_chest() {
+    // Too close to a monster
+    assert_eq!(
+      issues(&["...", ".$.", "..@"], None),
+      vec![Issue::NoRoomForChest(Coord::new(1, 1))]
+    );
+    // Too close to the edge of the board
+    assert_eq!(
+      issues(&["$.", "..", "@."], None),
+      vec![Issue::NoRoomForChest(Coord::new(0, 0))]
+    );
+    // Right next to another chest, on a board just big enough for one room
+    assert_eq!(
+      issues(&["$$.", "...", "..."], None),
+      vec![
+        Issue::NoRoomForChest(Coord::new(0, 0)),
+        Issue::NoRoomForChest(Coord::new(1, 0)),
+      ]
+    );
+  }
+
+  #[test]
+  fn adjacent_monsters() {
+    let diagnostics = {
+      let (puzzle, _) = board(&["@@.", "..@", "..@"]);
+      Level::new(puzzle, "Test".to_string(), String::new()).validate()
+    };
+    assert_eq!(
+      diagnostics,
+      vec![
+        Diagnostic::new(
+          Severity::Warning,
+          Issue::AdjacentMonsters(Coord::new(0, 0), Coord::new(1, 0))
+        ),
+        Diagnostic::new(
+          Severity::Warning,
+          Issue::AdjacentMonsters(Coord::new(2, 1), Coord::new(2, 2))
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn no_tiles() {
+    assert_eq!(issues(&["..", ".."], None), vec![Issue::NoTiles]);
+  }
+}
@@ -0,0 +1,191 @@
+//! The player's settings, from `config.toml` in their config directory.
+//!
+//! ```toml
+//! # One of "default", "high-contrast", "colorblind", "monochrome" or
+//! # "unicode".
+//! theme = "colorblind"
+//!
+//! # Each action takes a key or a list of keys, replacing its defaults.
+//! # Shifted letters are written in uppercase; Ctrl and Alt are written like
+//! # "ctrl+z".
+//! [keys]
+//! left = ["left", "d"]
+//! right = ["right", "n"]
+//! up = ["up", "t"]
+//! down = ["down", "h"]
+//! ```
+//!
+//! Actions are `left`, `right`, `up`, `down`, `wall`, `empty`, `wall_pen`,
+//! `empty_pen`, `erase_pen`, `lift_pen`, `fill_row`, `fill_column`, `hint`,
+//! `undo`, `redo`, `roll_back`, `reveal`, `redraw` and `quit`.
+//!
+//! A key can only do one thing, so taking a key another action has by default
+//! means giving that action different keys too.
+
+use std::{
+  collections::BTreeMap,
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+use eyre::{eyre, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+  keys::{Action, KeyBinding, Keymap},
+  theme::Theme,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+  pub keys: Keymap,
+  pub theme: Theme,
+}
+
+/// The file as written.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+  theme: Option<String>,
+  keys: BTreeMap<String, OneOrMany>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+  One(String),
+  Many(Vec<String>),
+}
+
+impl Config {
+  /// Load the config from `path`, or from the usual place if that's `None`.
+  ///
+  /// It's fine for the usual file not to exist, but not one that was asked
+  /// for.
+  pub fn load(path: Option<&str>) -> eyre::Result<Config> {
+    let (path, must_exist) = match path {
+      Some(path) => (PathBuf::from(path), true),
+      None => match default_path() {
+        Some(path) => (path, false),
+        None => return Ok(Config::default()),
+      },
+    };
+    let src = match fs::read_to_string(&path) {
+      Ok(src) => src,
+      Err(e) if e.kind() == io::ErrorKind::NotFound && !must_exist => {
+        return Ok(Config::default())
+      }
+      Err(e) => {
+        return Err(e)
+          .wrap_err_with(|| format!("couldn't read {}", path.display()))
+      }
+    };
+    Self::parse(&src).wrap_err_with(|| format!("in {}", path.display()))
+  }
+
+  fn parse(src: &str) -> eyre::Result<Config> {
+    let file: ConfigFile = toml::from_str(src)?;
+    let mut config = Config::default();
+
+    if let Some(name) = file.theme {
+      config.theme = Theme::by_name(&name).ok_or_else(|| {
+        eyre!(
+          "unknown theme {:?}, expected one of {}",
+          name,
+          Theme::NAMES.join(", ")
+        )
+      })?;
+    }
+
+    for (name, keys) in file.keys {
+      let action = Action::from_name(&name)
+        .ok_or_else(|| eyre!("unknown action {:?}", name))?;
+      let keys = match keys {
+        OneOrMany::One(key) => vec![key],
+        OneOrMany::Many(keys) => keys,
+      };
+      let keys = keys
+        .iter()
+        .map(|key| KeyBinding::parse(key))
+        .collect::<eyre::Result<_>>()?;
+      config.keys.bind(action, keys);
+    }
+    if let Some((key, a, b)) = config.keys.conflict() {
+      return Err(eyre!(
+        "{} is bound to both {} and {}, give one of them other keys",
+        key,
+        a.name(),
+        b.name()
+      ));
+    }
+
+    Ok(config)
+  }
+}
+
+fn default_path() -> Option<PathBuf> {
+  Some(
+    dirs::config_dir()?
+      .join("terminal-tombcrawler")
+      .join(Path::new("config.toml")),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::{KeyCode, KeyModifiers};
+
+  use super::*;
+
+  fn err(src: &str) -> String {
+    Config::parse(src).err().unwrap().to_string()
+  }
+
+  #[test]
+  fn remaps_keys() {
+    let config = Config::parse(
+      "theme = \"unicode\"\n[keys]\nleft = [\"left\", \"d\"]\ndown = \"h\"\n",
+    )
+    .unwrap();
+    assert!(config.theme.box_walls);
+    let action = |ch| config.keys.action(KeyCode::Char(ch), KeyModifiers::NONE);
+    assert_eq!(action('d'), Some(Action::Left));
+    assert_eq!(action('h'), Some(Action::Down));
+    assert_eq!(action('j'), None);
+    // Everything else keeps its defaults
+    assert_eq!(action('q'), Some(Action::Wall));
+  }
+
+  #[test]
+  fn empty_is_default() {
+    let config = Config::parse("").unwrap();
+    assert!(!config.theme.box_walls);
+    assert_eq!(config.keys.conflict(), None);
+  }
+
+  #[test]
+  fn unknown_names() {
+    assert_eq!(err("[keys]\njump = \"j\"\n"), "unknown action \"jump\"");
+    assert!(err("theme = \"neon\"\n").starts_with("unknown theme \"neon\""));
+    assert_eq!(
+      err("[keys]\nwall = \"hyper+q\"\n"),
+      "unknown modifier \"hyper\" in key \"hyper+q\""
+    );
+    // and typos outside the tables
+    assert!(Config::parse("theem = \"unicode\"\n").is_err());
+  }
+
+  #[test]
+  fn conflicts() {
+    assert_eq!(
+      err("[keys]\nwall = \"h\"\n"),
+      "h is bound to both left and wall, give one of them other keys"
+    );
+    assert_eq!(
+      err("[keys]\nredo = \"ctrl+Z\"\n"),
+      "Ctrl+Z is bound to both undo and redo, give one of them other keys"
+    );
+    // Taking the key away from the other action is fine
+    assert!(Config::parse("[keys]\nwall = \"h\"\nleft = \"left\"\n").is_ok());
+  }
+}
@@ -1,22 +1,40 @@
 //! Solver harness
 
-use std::io::{self, Stdout, Write};
+use std::{
+  io::{self, Stdout, Write},
+  time::{Duration, Instant},
+};
 
 use aglet::{Coord, Direction4, Grid};
 use crossterm::{
   cursor::MoveTo,
-  event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
-  style::{
-    Attribute, Attributes, Color, Colors, Print, ResetColor, SetAttributes,
-    SetColors, SetForegroundColor,
+  event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode,
+    KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
   },
+  style::{Attribute, Attributes, Print, ResetColor, SetAttribute},
   terminal::{
     disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
     LeaveAlternateScreen,
   },
   QueueableCommand,
 };
-use terminal_tombcrawler::{checker::Failure, Level, Solution, Tile};
+use terminal_tombcrawler::{
+  board::{Board, Cell},
+  checker::{Failure, FailureReason},
+  difficulty::Difficulty,
+  hint::Line,
+  incremental::IncrementalChecker,
+  Level, Solution,
+};
+
+use crate::{
+  config::Config,
+  history::{Edit, History},
+  keys::Action,
+  save::{format_elapsed, SaveState},
+  theme::{self, Text},
+};
 
 const START_X: u16 = 2;
 const START_Y: u16 = 2;
@@ -25,62 +43,122 @@ const TILE_STRIDE_X: u16 = 2;
 const TILE_STRIDE_Y: u16 = 2;
 
 /// This defines the position the HINTS are drawn at;
-/// the board is drawn one span below.
+/// the board is drawn one span below. Top hints taller than two digits push
+/// it further down, see `SolveHarness::board_y`.
 const BOARD_X: u16 = 4;
 const BOARD_Y: u16 = 6;
 
-pub struct SolveHarness {
+pub struct SolveHarness<'c> {
+  config: &'c Config,
   level: Level,
+  /// Worked out once up front; `None` if the level can't be solved.
+  difficulty: Option<Difficulty>,
   cursor: Coord,
 
-  markings: Grid<Marking>,
+  board: Board,
+  /// Kept in sync with `board`.
+  checker: IncrementalChecker,
+  history: History,
 
   solved: SolvedState,
+  /// Shown under the board until the next keypress.
+  message: Option<String>,
+  /// Whether Ctrl+R was used.
+  revealed: bool,
+  /// Whether this was ever solved, including in earlier sessions.
+  ever_solved: bool,
+  /// Time played before the clock was last started.
+  elapsed: Duration,
+  /// When the clock was started, if it's running. It stops once solved.
+  clock_started: Option<Instant>,
+  /// Whether the board changed since the last save.
+  dirty: bool,
+  /// The mouse drag or pen stroke going on, if any.
+  stroke: Option<Stroke>,
 
   must_redraw: bool,
 }
 
-impl SolveHarness {
-  /// Transfer runtime to the harness.
-  /// This will only return once the player is through.
-  pub fn enter(level: Level) -> io::Result<()> {
-    let markings = Grid::new(level.puzzle().width(), level.puzzle().height());
+impl<'c> SolveHarness<'c> {
+  /// Transfer runtime to the harness, picking up from `save`.
+  /// This will only return once the player is through, with how far they
+  /// got. That's also saved as they go.
+  pub fn enter(
+    config: &'c Config,
+    level: Level,
+    save: SaveState,
+  ) -> io::Result<SaveState> {
+    let board = save.board;
+    let checker = IncrementalChecker::new(level.puzzle(), &board);
 
     let mut harness = Self {
+      config,
+      difficulty: level.difficulty(),
       level,
       cursor: Coord::new(0, 0),
-      markings,
+      board,
+      checker,
+      history: History::new(),
       solved: SolvedState::JustStarted,
+      message: None,
+      revealed: save.revealed,
+      ever_solved: save.solved,
+      elapsed: save.elapsed,
+      clock_started: (!save.solved).then(Instant::now),
+      dirty: false,
+      stroke: None,
       must_redraw: false,
     };
 
     harness.spin()?;
 
-    Ok(())
+    let save = harness.save_state();
+    save.store()?;
+    Ok(save)
+  }
+
+  fn save_state(&self) -> SaveState {
+    SaveState {
+      board: self.board.clone(),
+      solved: self.ever_solved,
+      revealed: self.revealed,
+      elapsed: self.elapsed(),
+    }
+  }
+
+  fn elapsed(&self) -> Duration {
+    self.elapsed + self.clock_started.map_or(Duration::ZERO, |t| t.elapsed())
   }
 
   fn spin(&mut self) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    stdout.queue(EnterAlternateScreen)?.flush()?;
+    stdout
+      .queue(EnterAlternateScreen)?
+      .queue(EnableMouseCapture)?
+      .flush()?;
 
     loop {
       self.draw(&mut stdout)?;
 
       match event::read()? {
-        Event::Key(ev) => {
-          if matches!(ev.kind, KeyEventKind::Press | KeyEventKind::Repeat) {
-            let quit = self.update(ev.code, ev.modifiers)?;
-            if quit {
-              break;
-            }
+        Event::Key(ev)
+          if matches!(ev.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+        {
+          let quit = self.update(ev.code, ev.modifiers)?;
+          if quit {
+            break;
           }
         }
+        Event::Mouse(ev) => self.mouse(ev),
         _ => {}
       }
     }
 
-    stdout.queue(LeaveAlternateScreen)?.flush()?;
+    stdout
+      .queue(DisableMouseCapture)?
+      .queue(LeaveAlternateScreen)?
+      .flush()?;
     disable_raw_mode()?;
 
     Ok(())
@@ -88,98 +166,401 @@ impl SolveHarness {
 
   /// return whether to quit
   fn update(&mut self, key: KeyCode, mods: KeyModifiers) -> io::Result<bool> {
-    let quit = 'inner: {
-      if key == KeyCode::Char('c') && mods.contains(KeyModifiers::CONTROL) {
-        break 'inner true;
+    let action = self.config.keys.action(key, mods);
+    if action == Some(Action::Quit) {
+      return Ok(true);
+    }
+
+    self.must_redraw = false;
+    self.message = None;
+    // Anything but moving the pen around finishes the stroke
+    let pen_action = matches!(
+      action,
+      Some(Action::WallPen | Action::EmptyPen | Action::ErasePen)
+    );
+    let direction = action.and_then(action_direction);
+    // and any key at all finishes a mouse drag, so its step goes in the
+    // history before whatever the key does
+    let dragging = self.stroke.as_ref().is_some_and(|s| s.button.is_some());
+    if dragging || (!pen_action && direction.is_none()) {
+      self.finish_stroke();
+    }
+
+    match action {
+      Some(Action::Redraw) => self.must_redraw = true,
+      Some(Action::Reveal) => {
+        self.reveal_solution();
+        self.revealed = true;
+      }
+      Some(Action::Hint) => self.show_hint(),
+      Some(Action::Undo) => {
+        self.undo();
+      }
+      Some(Action::Redo) => self.redo(),
+      Some(Action::RollBack) => self.roll_back(),
+      Some(Action::WallPen) => self.toggle_pen(Cell::Wall),
+      Some(Action::EmptyPen) => self.toggle_pen(Cell::Open),
+      Some(Action::ErasePen) => self.toggle_pen(Cell::Unknown),
+      Some(Action::FillRow) => self.fill_line(Line::Row(self.cursor.y)),
+      Some(Action::FillColumn) => self.fill_line(Line::Column(self.cursor.x)),
+      Some(Action::Wall) => self.mark(Cell::Wall),
+      Some(Action::Empty) => self.mark(Cell::Open),
+      Some(Action::Left | Action::Right | Action::Up | Action::Down) => {
+        if let Some(dir) = direction {
+          self.move_cursor(dir, mods.contains(KeyModifiers::SHIFT));
+        }
       }
+      Some(Action::LiftPen | Action::Quit) | None => {}
+    }
+
+    self.refresh();
+    Ok(false)
+  }
 
-      if self.must_redraw {
-        self.must_redraw = false;
+  /// Move the cursor one step, wrapping around, or all the way to the edge
+  /// if `snap`. The pen paints along the way.
+  fn move_cursor(&mut self, dir: Direction4, snap: bool) {
+    let width = self.level.puzzle().width();
+    let height = self.level.puzzle().height();
+
+    let x2 = match (dir, snap) {
+      (Direction4::North | Direction4::South, _) => self.cursor.x,
+      (Direction4::West, false) => {
+        // do subtraction by wrapping around, thanks finite fields
+        (self.cursor.x + width - 1).rem_euclid(width)
       }
-      if key == KeyCode::Char('l') && mods.contains(KeyModifiers::CONTROL) {
-        self.must_redraw = true;
-        break 'inner false;
+      (Direction4::West, true) => 0,
+      (Direction4::East, false) => (self.cursor.x + 1).rem_euclid(width),
+      (Direction4::East, true) => width - 1,
+    };
+    let y2 = match (dir, snap) {
+      (Direction4::West | Direction4::East, _) => self.cursor.y,
+      (Direction4::North, false) => {
+        (self.cursor.y + height - 1).rem_euclid(height)
       }
+      (Direction4::North, true) => 0,
+      (Direction4::South, false) => (self.cursor.y + 1).rem_euclid(height),
+      (Direction4::South, true) => height - 1,
+    };
 
-      let width = self.level.puzzle().width();
-      let height = self.level.puzzle().height();
+    let to = Coord::new(x2, y2);
+    if let Some(mut stroke) = self.stroke.take() {
+      // Don't paint all the way back across the board
+      let wrapped =
+        self.cursor.x.abs_diff(x2) + self.cursor.y.abs_diff(y2) > 1 && !snap;
+      if !wrapped {
+        self.stroke_to(&mut stroke, to);
+        self.cursor = to;
+      }
+      self.stroke = Some(stroke);
+    } else {
+      self.cursor = to;
+    }
+  }
 
-      let cursor_delta = match key {
-        KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('H') => {
-          Some(Direction4::West)
-        }
-        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('L') => {
-          Some(Direction4::East)
-        }
-        KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('K') => {
-          Some(Direction4::North)
-        }
-        KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('J') => {
-          Some(Direction4::South)
-        }
-        _ => None,
-      };
-      if let Some(cursor_delta) = cursor_delta {
-        let x2 = match (cursor_delta, mods.contains(KeyModifiers::SHIFT)) {
-          (Direction4::North | Direction4::South, _) => self.cursor.x,
-          (Direction4::West, false) => {
-            // do subtraction by wrapping around, thanks finite fields
-            (self.cursor.x + width - 1).rem_euclid(width)
-          }
-          (Direction4::West, true) => 0,
-          (Direction4::East, false) => (self.cursor.x + 1).rem_euclid(width),
-          (Direction4::East, true) => width - 1,
+  /// Toggle `cell` under the cursor.
+  fn mark(&mut self, cell: Cell) {
+    if self.level.puzzle().get_tile(self.cursor).is_some() {
+      return;
+    }
+    let cell = if self.board.get(self.cursor) == cell {
+      Cell::Unknown
+    } else {
+      cell
+    };
+    self.apply(vec![(self.cursor, cell)]);
+  }
+
+  /// Put the pen down painting `paint`, or lift it if it already was.
+  fn toggle_pen(&mut self, paint: Cell) {
+    let was_painting = self
+      .stroke
+      .as_ref()
+      .filter(|stroke| stroke.button.is_none())
+      .map(|stroke| stroke.paint);
+    self.finish_stroke();
+    if was_painting == Some(paint) {
+      return;
+    }
+    let mut stroke = Stroke {
+      button: None,
+      paint,
+      last: self.cursor,
+      step: Vec::new(),
+    };
+    self.paint(&mut stroke, self.cursor);
+    self.stroke = Some(stroke);
+  }
+
+  /// Finish the pen or mouse stroke going on, if any, as one undo step.
+  fn finish_stroke(&mut self) {
+    if let Some(stroke) = self.stroke.take() {
+      self.history.record(stroke.step);
+    }
+  }
+
+  /// Once a line has all its walls, mark the rest of it as empty.
+  fn fill_line(&mut self, line: Line) {
+    let puzzle = self.level.puzzle();
+    let (cells, hint, walls) = match line {
+      Line::Row(y) => (
+        (0..puzzle.width())
+          .map(|x| Coord::new(x, y))
+          .collect::<Vec<_>>(),
+        puzzle.side_hints()[y as usize] as u32,
+        self.board.row_wall_count(y),
+      ),
+      Line::Column(x) => (
+        (0..puzzle.height()).map(|y| Coord::new(x, y)).collect(),
+        puzzle.top_hints()[x as usize] as u32,
+        self.board.column_wall_count(x),
+      ),
+    };
+    if walls < hint {
+      self.message = Some(format!(
+        "{} only has {} of its {} walls so far",
+        line, walls, hint
+      ));
+      return;
+    } else if walls > hint {
+      self.message = Some(format!("{} has too many walls", line));
+      return;
+    }
+    let changes = cells
+      .into_iter()
+      .filter(|&c| {
+        puzzle.get_tile(c).is_none() && self.board.get(c) == Cell::Unknown
+      })
+      .map(|c| (c, Cell::Open))
+      .collect();
+    self.apply(changes);
+  }
+
+  /// Left click toggles a wall and right click an empty mark. Dragging paints
+  /// whatever the click did over every cell it passes, as one undo step.
+  fn mouse(&mut self, ev: MouseEvent) {
+    let coord = self.screen_to_grid(ev.column, ev.row).filter(|&c| {
+      c.x < self.level.puzzle().width() && c.y < self.level.puzzle().height()
+    });
+    match ev.kind {
+      MouseEventKind::Down(button) => {
+        let (coord, mark) = match (coord, button) {
+          (Some(coord), MouseButton::Left) => (coord, Cell::Wall),
+          (Some(coord), MouseButton::Right) => (coord, Cell::Open),
+          _ => return,
+        };
+        self.message = None;
+        // Including a drag whose release we never saw
+        self.finish_stroke();
+        self.cursor = coord;
+        let paint = if self.board.get(coord) == mark {
+          Cell::Unknown
+        } else {
+          mark
+        };
+        let mut stroke = Stroke {
+          button: Some(button),
+          paint,
+          last: coord,
+          step: Vec::new(),
         };
-        let y2 = match (cursor_delta, mods.contains(KeyModifiers::SHIFT)) {
-          (Direction4::West | Direction4::East, _) => self.cursor.y,
-          (Direction4::North, false) => {
-            (self.cursor.y + height - 1).rem_euclid(height)
+        self.paint(&mut stroke, coord);
+        self.stroke = Some(stroke);
+      }
+      MouseEventKind::Drag(button) => {
+        let (mut stroke, coord) = match (self.stroke.take(), coord) {
+          (Some(stroke), Some(coord)) if stroke.button == Some(button) => {
+            (stroke, coord)
+          }
+          (stroke, _) => {
+            self.stroke = stroke;
+            return;
           }
-          (Direction4::North, true) => 0,
-          (Direction4::South, false) => (self.cursor.y + 1).rem_euclid(height),
-          (Direction4::South, true) => height - 1,
         };
+        self.stroke_to(&mut stroke, coord);
+        self.cursor = coord;
+        self.stroke = Some(stroke);
+      }
+      MouseEventKind::Up(_) => match self.stroke.take() {
+        Some(stroke) if stroke.button.is_some() => {
+          self.history.record(stroke.step)
+        }
+        other => {
+          self.stroke = other;
+          return;
+        }
+      },
+      _ => return,
+    }
+    self.refresh();
+  }
+
+  /// Paint every cell from the end of the stroke up to `to`, so nothing
+  /// gets skipped.
+  fn stroke_to(&mut self, stroke: &mut Stroke, to: Coord) {
+    let mut here = stroke.last;
+    while here != to {
+      here = Coord::new(step_toward(here.x, to.x), step_toward(here.y, to.y));
+      self.paint(stroke, here);
+    }
+    stroke.last = to;
+  }
+
+  fn paint(&mut self, stroke: &mut Stroke, coord: Coord) {
+    if self.level.puzzle().get_tile(coord).is_some() {
+      return;
+    }
+    let before = self.set_cell(coord, stroke.paint);
+    if before != stroke.paint {
+      stroke.step.push(Edit {
+        coord,
+        before,
+        after: stroke.paint,
+      });
+    }
+  }
 
-        self.cursor = Coord::new(x2, y2);
-        break 'inner false;
+  /// Catch up on whatever the last change did.
+  fn refresh(&mut self) {
+    self.solved = if self.checker.is_solved() {
+      SolvedState::Success
+    } else {
+      SolvedState::Fail(self.checker.failures())
+    };
+    if matches!(self.solved, SolvedState::Success) && !self.revealed {
+      self.elapsed = self.elapsed();
+      self.clock_started = None;
+      self.ever_solved = true;
+    }
+    if self.dirty {
+      self.dirty = false;
+      if let Err(e) = self.save_state().store() {
+        self.message = Some(format!("couldn't save: {}", e));
       }
+    }
+  }
 
-      // Try markings
-      if self.level.puzzle().get_tile(self.cursor).is_none() {
-        let marking_here = self.markings.get(self.cursor).copied();
-        let wanted_marking = match key {
-          KeyCode::Char('q') => Ok(if marking_here == Some(Marking::Wall) {
-            None
-          } else {
-            Some(Marking::Wall)
-          }),
-          KeyCode::Char('w') => Ok(if marking_here == None {
-            Some(Marking::Empty)
-          } else {
-            None
-          }),
-          _ => Err(()),
-        };
-        if let Ok(marking2) = wanted_marking {
-          self.markings.insert_direct(self.cursor, marking2);
-          break 'inner false;
+  /// Replace the player's markings with the stored solution, or one from
+  /// the solver if the level didn't come with one.
+  fn reveal_solution(&mut self) {
+    let puzzle = self.level.puzzle();
+    let solved;
+    let solution = match self.level.solution() {
+      Some(it) => it,
+      None => match puzzle.solve() {
+        Some(it) => {
+          solved = it;
+          &solved
         }
+        None => return,
+      },
+    };
+    let changes = self
+      .board
+      .coords()
+      .map(|coord| {
+        let cell = if solution.is_wall(coord) {
+          Cell::Wall
+        } else {
+          Cell::Unknown
+        };
+        (coord, cell)
+      })
+      .collect();
+    self.apply(changes);
+  }
+
+  /// Make some changes as one step the player can undo.
+  fn apply(&mut self, changes: Vec<(Coord, Cell)>) {
+    let mut step = Vec::new();
+    for (coord, after) in changes {
+      let before = self.set_cell(coord, after);
+      if before != after {
+        step.push(Edit {
+          coord,
+          before,
+          after,
+        });
       }
+    }
+    self.history.record(step);
+  }
 
-      false
-    };
-    let view = SolutionView {
-      marks: &self.markings,
+  fn undo(&mut self) -> bool {
+    let step = match self.history.undo() {
+      Some(it) => it.to_vec(),
+      None => {
+        self.message = Some("nothing to undo".to_string());
+        return false;
+      }
     };
-    let solved = self.level.puzzle().check_solution(&view, false);
-    self.solved = match solved {
-      Ok(()) => SolvedState::Success,
-      Err(fail) => SolvedState::Fail(fail),
+    for edit in step.iter().rev() {
+      self.set_cell(edit.coord, edit.before);
+    }
+    if let [edit] = step[..] {
+      self.cursor = edit.coord;
+    }
+    true
+  }
+
+  fn redo(&mut self) {
+    let step = match self.history.redo() {
+      Some(it) => it.to_vec(),
+      None => {
+        self.message = Some("nothing to redo".to_string());
+        return;
+      }
     };
-    Ok(quit)
+    for edit in step.iter() {
+      self.set_cell(edit.coord, edit.after);
+    }
+    if let [edit] = step[..] {
+      self.cursor = edit.coord;
+    }
+  }
+
+  /// Undo until the board is back to a state the rules are fine with, like
+  /// before a guess that went wrong. Each step can still be redone.
+  fn roll_back(&mut self) {
+    let mut steps = 0;
+    while !self.level.puzzle().is_consistent(&self.board) && self.undo() {
+      steps += 1;
+    }
+    let consistent = self.level.puzzle().is_consistent(&self.board);
+    self.message = Some(match (steps, consistent) {
+      (0, true) => "nothing to roll back: everything still adds up".to_string(),
+      (_, false) => {
+        "couldn't find a point where everything adds up".to_string()
+      }
+      (1, true) => "rolled back 1 step".to_string(),
+      (_, true) => format!("rolled back {} steps", steps),
+    });
+  }
+
+  /// Move the cursor to the next cell that can be deduced and explain it.
+  fn show_hint(&mut self) {
+    self.message = Some(match self.level.puzzle().hint(&self.board) {
+      Some(hint) => {
+        self.cursor = hint.coord;
+        hint.to_string()
+      }
+      None if self.checker.is_solved() => "nothing left to do!".to_string(),
+      None => {
+        "no hints: a mark might be wrong, or you'll have to guess".to_string()
+      }
+    });
+  }
+
+  /// Returns what was there before.
+  fn set_cell(&mut self, coord: Coord, cell: Cell) -> Cell {
+    let before = self.board.set(coord, cell);
+    self.checker.set_wall(coord, cell == Cell::Wall);
+    self.dirty |= before != cell;
+    before
   }
 
   fn draw(&self, stdout: &mut Stdout) -> io::Result<()> {
+    let theme = &self.config.theme;
     if self.must_redraw {
       stdout.queue(Clear(ClearType::All))?;
     }
@@ -187,183 +568,259 @@ impl SolveHarness {
     stdout.queue(MoveTo(START_X, START_Y))?;
     stdout
       .queue(ResetColor)?
+      .queue(SetAttribute(Attribute::Reset))?
       .queue(Print(&self.level.title()))?;
+    let rating = match &self.difficulty {
+      Some(it) => it.to_string(),
+      None => "unsolvable!".to_string(),
+    };
+    stdout.queue(MoveTo(START_X, START_Y + 1))?;
+    theme::set_text(stdout, &theme.faint)?;
+    stdout.queue(Print(rating))?;
+    let pen = match self.stroke {
+      Some(Stroke {
+        button: None,
+        paint,
+        ..
+      }) => {
+        let what = match paint {
+          Cell::Wall => "walls",
+          Cell::Open => "free spaces",
+          Cell::Unknown => "erasing",
+        };
+        format!(
+          "pen down: {} ({} to lift)",
+          what,
+          self.config.keys.describe(Action::LiftPen)
+        )
+      }
+      _ => String::new(),
+    };
+    stdout
+      .queue(MoveTo(START_X, START_Y + 2))?
+      .queue(Print(pen))?
+      .queue(Clear(ClearType::UntilNewLine))?;
 
-    let (col_counts, row_counts) = self.col_row_wall_counts();
+    // Columns are only two wide, so longer hints stack upwards
+    let board_y = self.board_y();
     for (x, &hint) in self.level.puzzle().top_hints().iter().enumerate() {
-      let col_count = col_counts[x] as u8;
-      let color = if col_count == hint {
-        Color::DarkGreen
-      } else if col_count > hint {
-        Color::Red
-      } else {
-        Color::White
-      };
-
-      stdout
-        .queue(MoveTo(BOARD_X + (x as u16 + 1) * TILE_STRIDE_X, BOARD_Y))?
-        .queue(SetForegroundColor(color))?
-        .queue(Print(hint))?;
+      let col_count = self.board.column_wall_count(x as u32) as u8;
+      theme::set_text(stdout, hint_style(theme, col_count, hint))?;
+      let screen_x = BOARD_X + (x as u16 + 1) * TILE_STRIDE_X;
+      for (i, digit) in hint.to_string().chars().rev().enumerate() {
+        stdout
+          .queue(MoveTo(screen_x, board_y - i as u16))?
+          .queue(Print(digit))?;
+      }
     }
+    // and side hints grow to the left
     for (y, &hint) in self.level.puzzle().side_hints().iter().enumerate() {
-      let row_count = row_counts[y] as u8;
-      let color = if row_count == hint {
-        Color::DarkGreen
-      } else if row_count > hint {
-        Color::Red
-      } else {
-        Color::White
-      };
-      stdout
-        .queue(MoveTo(BOARD_X, BOARD_Y + (y as u16 + 1) * TILE_STRIDE_Y))?
-        .queue(SetForegroundColor(color))?
-        .queue(Print(hint))?;
+      let row_count = self.board.row_wall_count(y as u32) as u8;
+      let digits = hint.to_string();
+      stdout.queue(MoveTo(
+        BOARD_X + 1 - digits.len() as u16,
+        board_y + (y as u16 + 1) * TILE_STRIDE_Y,
+      ))?;
+      theme::set_text(stdout, hint_style(theme, row_count, hint))?;
+      stdout.queue(Print(digits))?;
     }
 
-    for y in 0..self.level.puzzle().height() {
-      for x in 0..self.level.puzzle().width() {
-        let coord = Coord::new(x as _, y as _);
-
-        let (ch, cols, fmt) =
-          if let Some(tile) = self.level.puzzle().get_tile(coord) {
-            puzzle_tile_display(tile)
-          } else if let Some(marking) = self.markings.get(coord) {
-            marking.display()
-          } else {
-            bg_display()
-          };
-        let screenpos = grid_to_screen(coord);
-        stdout
-          .queue(MoveTo(screenpos.0, screenpos.1))?
-          .queue(SetColors(cols))?
-          .queue(SetAttributes(fmt))?
-          .queue(Print(ch))?;
+    // Underline everything involved in a problem. Wrong hint counts are
+    // already shown on the hints themselves.
+    let mut problems =
+      Grid::new(self.level.puzzle().width(), self.level.puzzle().height());
+    if let SolvedState::Fail(ref onos) = self.solved {
+      for ono in onos {
+        if !matches!(
+          ono.reason,
+          FailureReason::RowWallCountMismatch(_)
+            | FailureReason::ColumnWallCountMismatch(_)
+        ) {
+          for &coord in ono.region.iter() {
+            problems.insert(coord, ());
+          }
+        }
+      }
+    }
+
+    let (width, height) =
+      (self.level.puzzle().width(), self.level.puzzle().height());
+    let is_wall = |x: u32, y: u32| {
+      x < width && y < height && self.board.get(Coord::new(x, y)) == Cell::Wall
+    };
+    for y in 0..height {
+      for x in 0..width {
+        let coord = Coord::new(x, y);
+
+        let mut glyph = match self.level.puzzle().get_tile(coord) {
+          Some(tile) => theme.tile(tile),
+          None => theme.cell(self.board.get(coord)),
+        };
+        let wall = is_wall(x, y);
+        let east = wall && is_wall(x + 1, y);
+        let south = wall && is_wall(x, y + 1);
+        if wall && theme.box_walls {
+          let north = y > 0 && is_wall(x, y - 1);
+          let west = x > 0 && is_wall(x - 1, y);
+          glyph.ch = theme::box_wall(north, east, south, west);
+        }
+        let extra = if problems.contains(coord) {
+          Attribute::Underlined.into()
+        } else {
+          Attributes::default()
+        };
+        let screenpos = self.grid_to_screen(coord);
+        stdout.queue(MoveTo(screenpos.0, screenpos.1))?;
+        theme::set_glyph(stdout, &glyph, extra)?;
+        stdout.queue(Print(glyph.ch))?;
+
+        // Join walls up across the gaps between tiles
+        if theme.box_walls {
+          if x + 1 < width {
+            stdout.queue(MoveTo(screenpos.0 + 1, screenpos.1))?;
+            theme::set_glyph(stdout, &theme.wall, Attributes::default())?;
+            stdout.queue(Print(if east { '─' } else { ' ' }))?;
+          }
+          if y + 1 < height {
+            stdout.queue(MoveTo(screenpos.0, screenpos.1 + 1))?;
+            theme::set_glyph(stdout, &theme.wall, Attributes::default())?;
+            stdout.queue(Print(if south { '│' } else { ' ' }))?;
+          }
+        }
       }
     }
 
     // Temp
-    let rightmost = grid_to_screen(Coord::new(self.level.puzzle().width(), 1));
+    let rightmost =
+      self.grid_to_screen(Coord::new(self.level.puzzle().width(), 1));
     match self.solved {
       SolvedState::JustStarted => {}
-      SolvedState::Fail(ref ono) => {
+      SolvedState::Fail(ref onos) => {
         stdout
-          .queue(MoveTo(rightmost.0, rightmost.1))?
           .queue(ResetColor)?
-          .queue(Print(format!("{:?}", ono.reason)))?
-          .queue(MoveTo(rightmost.0, rightmost.1 + 1))?
-          .queue(Print(ono.pos))?;
+          .queue(SetAttribute(Attribute::Reset))?;
+        let room = self.level.puzzle().height() as usize * 2;
+        for (i, ono) in onos.iter().take(room).enumerate() {
+          stdout
+            .queue(MoveTo(rightmost.0, rightmost.1 + i as u16))?
+            .queue(Print(format!("{} at {}", ono.reason, ono.pos)))?
+            .queue(Clear(ClearType::UntilNewLine))?;
+        }
+        for i in onos.len()..room {
+          stdout
+            .queue(MoveTo(rightmost.0, rightmost.1 + i as u16))?
+            .queue(Clear(ClearType::UntilNewLine))?;
+        }
       }
       SolvedState::Success => {
+        stdout.queue(MoveTo(rightmost.0, rightmost.1))?;
+        theme::set_text(stdout, &theme.good)?;
         stdout
-          .queue(MoveTo(rightmost.0, rightmost.1))?
-          .queue(SetForegroundColor(Color::Green))?
-          .queue(Print("yay!"))?;
+          .queue(Print(format!("yay! ({})", format_elapsed(self.elapsed()))))?
+          .queue(Clear(ClearType::UntilNewLine))?;
+        for i in 1..self.level.puzzle().height() as u16 * 2 {
+          stdout
+            .queue(MoveTo(rightmost.0, rightmost.1 + i))?
+            .queue(Clear(ClearType::UntilNewLine))?;
+        }
       }
     }
 
-    let cursorpos = grid_to_screen(self.cursor);
+    // Under the board and the failure list
+    let message_y = rightmost.1 + self.level.puzzle().height() as u16 * 2;
+    stdout
+      .queue(MoveTo(START_X, message_y))?
+      .queue(ResetColor)?
+      .queue(SetAttribute(Attribute::Reset))?
+      .queue(Print(self.message.as_deref().unwrap_or("")))?
+      .queue(Clear(ClearType::UntilNewLine))?;
+
+    let cursorpos = self.grid_to_screen(self.cursor);
     stdout.queue(MoveTo(cursorpos.0, cursorpos.1))?;
 
     stdout.flush()?;
     Ok(())
   }
-
-  fn col_row_wall_counts(&self) -> (Vec<usize>, Vec<usize>) {
-    // i recognize there's some O(n) way to do this but i don't care
-    let col_counts = (0..self.level.puzzle().width())
-      .map(|x| {
-        (0..self.level.puzzle().height())
-          .filter(|&y| {
-            self.markings.get(Coord::new(x as _, y as _)).copied()
-              == Some(Marking::Wall)
-          })
-          .count()
-      })
-      .collect();
-    let row_counts = (0..self.level.puzzle().height())
-      .map(|y| {
-        (0..self.level.puzzle().width())
-          .filter(|&x| {
-            self.markings.get(Coord::new(x as _, y as _)).copied()
-              == Some(Marking::Wall)
-          })
-          .count()
-      })
-      .collect();
-
-    (col_counts, row_counts)
-  }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Marking {
-  Wall,
-  Empty,
-}
-
-impl Marking {
-  fn display(&self) -> (char, Colors, Attributes) {
-    match self {
-      Marking::Wall => (
-        '#',
-        Colors::new(Color::White, Color::DarkGrey),
-        Attributes::default() | Attribute::NormalIntensity | Attribute::Bold,
-      ),
-      Marking::Empty => (
-        '*',
-        Colors::new(Color::DarkMagenta, Color::Reset),
-        Attributes::default() | Attribute::NormalIntensity | Attribute::Italic,
-      ),
-    }
-  }
 }
 
 enum SolvedState {
   JustStarted,
   /// Temporarily display to the player
-  Fail(Failure),
+  Fail(Vec<Failure>),
   Success,
 }
 
-fn puzzle_tile_display(tile: Tile) -> (char, Colors, Attributes) {
-  match tile {
-    Tile::Monster => (
-      '@',
-      Colors::new(Color::Red, Color::Reset),
-      Attributes::default() | Attribute::Bold | Attribute::NoItalic,
-    ),
-    Tile::TreasureChest => (
-      '$',
-      Colors::new(Color::Yellow, Color::Reset),
-      Attributes::default() | Attribute::Bold | Attribute::NoItalic,
-    ),
+/// How to show a hint, given how many walls its line has.
+fn hint_style(theme: &theme::Theme, walls: u8, hint: u8) -> &Text {
+  if walls == hint {
+    &theme.hint_met
+  } else if walls > hint {
+    &theme.hint_over
+  } else {
+    &theme.hint_under
   }
 }
 
-fn bg_display() -> (char, Colors, Attributes) {
-  (
-    '.',
-    Colors::new(Color::DarkGrey, Color::Reset),
-    Attribute::NormalIntensity.into(),
-  )
-}
+impl SolveHarness<'_> {
+  /// Where the top hints end. Two digits fit between the status lines and
+  /// `BOARD_Y`; each one past that moves everything below down a line.
+  fn board_y(&self) -> u16 {
+    let tallest = self
+      .level
+      .puzzle()
+      .top_hints()
+      .iter()
+      .map(|hint| hint.to_string().len() as u16)
+      .max()
+      .unwrap_or(1);
+    BOARD_Y + tallest.saturating_sub(2)
+  }
 
-fn grid_to_screen(coord: Coord) -> (u16, u16) {
-  (
-    (coord.x as u16 + 1) * TILE_STRIDE_X + BOARD_X,
-    (coord.y as u16 + 1) * TILE_STRIDE_Y + BOARD_Y,
-  )
+  fn grid_to_screen(&self, coord: Coord) -> (u16, u16) {
+    (
+      (coord.x as u16 + 1) * TILE_STRIDE_X + BOARD_X,
+      (coord.y as u16 + 1) * TILE_STRIDE_Y + self.board_y(),
+    )
+  }
+
+  /// The inverse of `grid_to_screen`. The gaps right of and below each tile
+  /// count as part of it, so near misses still land. This doesn't know how
+  /// big the board is.
+  fn screen_to_grid(&self, x: u16, y: u16) -> Option<Coord> {
+    let gx = x.checked_sub(BOARD_X)? / TILE_STRIDE_X;
+    let gy = y.checked_sub(self.board_y())? / TILE_STRIDE_Y;
+    Some(Coord::new(
+      gx.checked_sub(1)? as u32,
+      gy.checked_sub(1)? as u32,
+    ))
+  }
 }
 
-struct SolutionView<'a> {
-  marks: &'a Grid<Marking>,
+fn action_direction(action: Action) -> Option<Direction4> {
+  match action {
+    Action::Left => Some(Direction4::West),
+    Action::Right => Some(Direction4::East),
+    Action::Up => Some(Direction4::North),
+    Action::Down => Some(Direction4::South),
+    _ => None,
+  }
 }
 
-impl<'a> Solution for SolutionView<'a> {
-  fn is_wall(&self, coord: Coord) -> bool {
-    match self.marks.get(coord) {
-      None => false,
-      Some(mark) => *mark == Marking::Wall,
-    }
+fn step_toward(from: u32, to: u32) -> u32 {
+  match from.cmp(&to) {
+    std::cmp::Ordering::Less => from + 1,
+    std::cmp::Ordering::Equal => from,
+    std::cmp::Ordering::Greater => from - 1,
   }
 }
+
+/// A mouse button held down over the board, or the pen put down.
+struct Stroke {
+  /// `None` for the pen.
+  button: Option<MouseButton>,
+  /// What every cell it passes over gets set to.
+  paint: Cell,
+  last: Coord,
+  /// Everything changed so far, to undo all at once.
+  step: Vec<Edit>,
+}
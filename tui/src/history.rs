@@ -0,0 +1,47 @@
+//! Undo and redo for the solve harness.
+
+use aglet::Coord;
+use terminal_tombcrawler::board::Cell;
+
+/// One cell changing.
+#[derive(Debug, Clone, Copy)]
+pub struct Edit {
+  pub coord: Coord,
+  pub before: Cell,
+  pub after: Cell,
+}
+
+/// Steps the player took, each of which may change many cells at once.
+#[derive(Debug, Default)]
+pub struct History {
+  undo: Vec<Vec<Edit>>,
+  redo: Vec<Vec<Edit>>,
+}
+
+impl History {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Remember a step. This forgets anything that could've been redone.
+  pub fn record(&mut self, step: Vec<Edit>) {
+    if !step.is_empty() {
+      self.undo.push(step);
+      self.redo.clear();
+    }
+  }
+
+  /// Take back the last step. Put each edit's `before` back, in reverse.
+  pub fn undo(&mut self) -> Option<&[Edit]> {
+    let step = self.undo.pop()?;
+    self.redo.push(step);
+    self.redo.last().map(Vec::as_slice)
+  }
+
+  /// Do the last undone step again. Put each edit's `after` back, in order.
+  pub fn redo(&mut self) -> Option<&[Edit]> {
+    let step = self.redo.pop()?;
+    self.undo.push(step);
+    self.undo.last().map(Vec::as_slice)
+  }
+}
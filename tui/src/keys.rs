@@ -0,0 +1,368 @@
+//! What each key does, so players can move things around.
+
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use eyre::eyre;
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  Left,
+  Right,
+  Up,
+  Down,
+  Wall,
+  Empty,
+  WallPen,
+  EmptyPen,
+  ErasePen,
+  LiftPen,
+  FillRow,
+  FillColumn,
+  Hint,
+  Undo,
+  Redo,
+  RollBack,
+  Reveal,
+  Redraw,
+  Quit,
+}
+
+impl Action {
+  pub const ALL: [Action; 19] = [
+    Action::Left,
+    Action::Right,
+    Action::Up,
+    Action::Down,
+    Action::Wall,
+    Action::Empty,
+    Action::WallPen,
+    Action::EmptyPen,
+    Action::ErasePen,
+    Action::LiftPen,
+    Action::FillRow,
+    Action::FillColumn,
+    Action::Hint,
+    Action::Undo,
+    Action::Redo,
+    Action::RollBack,
+    Action::Reveal,
+    Action::Redraw,
+    Action::Quit,
+  ];
+
+  /// What it's called in the config file.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Action::Left => "left",
+      Action::Right => "right",
+      Action::Up => "up",
+      Action::Down => "down",
+      Action::Wall => "wall",
+      Action::Empty => "empty",
+      Action::WallPen => "wall_pen",
+      Action::EmptyPen => "empty_pen",
+      Action::ErasePen => "erase_pen",
+      Action::LiftPen => "lift_pen",
+      Action::FillRow => "fill_row",
+      Action::FillColumn => "fill_column",
+      Action::Hint => "hint",
+      Action::Undo => "undo",
+      Action::Redo => "redo",
+      Action::RollBack => "roll_back",
+      Action::Reveal => "reveal",
+      Action::Redraw => "redraw",
+      Action::Quit => "quit",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Action> {
+    Action::ALL.into_iter().find(|a| a.name() == name)
+  }
+
+  /// Moving the cursor ignores case, so holding shift can snap to the edge.
+  fn is_movement(&self) -> bool {
+    matches!(
+      self,
+      Action::Left | Action::Right | Action::Up | Action::Down
+    )
+  }
+
+  fn default_keys(&self) -> &'static [&'static str] {
+    match self {
+      Action::Left => &["left", "h"],
+      Action::Right => &["right", "l"],
+      Action::Up => &["up", "k"],
+      Action::Down => &["down", "j"],
+      Action::Wall => &["q"],
+      Action::Empty => &["w"],
+      Action::WallPen => &["Q"],
+      Action::EmptyPen => &["W"],
+      Action::ErasePen => &["E"],
+      Action::LiftPen => &["esc"],
+      Action::FillRow => &["r"],
+      Action::FillColumn => &["c"],
+      Action::Hint => &["?"],
+      Action::Undo => &["u", "ctrl+z"],
+      Action::Redo => &["U", "ctrl+y"],
+      Action::RollBack => &["b"],
+      Action::Reveal => &["ctrl+r"],
+      Action::Redraw => &["ctrl+l"],
+      Action::Quit => &["ctrl+c"],
+    }
+  }
+}
+
+/// One key, maybe with Ctrl or Alt held. Shift is part of the character,
+/// so `Q` is Shift+Q.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+  code: KeyCode,
+  ctrl: bool,
+  alt: bool,
+}
+
+impl KeyBinding {
+  /// Parse something like `q`, `Q`, `ctrl+z`, `left` or `esc`.
+  pub fn parse(s: &str) -> eyre::Result<KeyBinding> {
+    let mut binding = KeyBinding {
+      code: KeyCode::Null,
+      ctrl: false,
+      alt: false,
+    };
+    let mut shift = false;
+    // `+` on its own, or at the end like `ctrl++`, is the plus key
+    let (mods, key) = match s.strip_suffix("++") {
+      Some(mods) => (mods, "+"),
+      None => match s.rsplit_once('+') {
+        Some((mods, key)) if !key.is_empty() => (mods, key),
+        _ => ("", s),
+      },
+    };
+    for m in mods.split('+').filter(|m| !m.is_empty()) {
+      match m.to_lowercase().as_str() {
+        "ctrl" => binding.ctrl = true,
+        "alt" => binding.alt = true,
+        "shift" => shift = true,
+        _ => return Err(eyre!("unknown modifier {:?} in key {:?}", m, s)),
+      }
+    }
+
+    let mut chars = key.chars();
+    binding.code = match (chars.next(), chars.next()) {
+      (Some(ch), None) if shift => KeyCode::Char(ch.to_ascii_uppercase()),
+      // Terminals send Ctrl+Z as a lowercase z, whatever was written
+      (Some(ch), None) if binding.ctrl || binding.alt => {
+        KeyCode::Char(ch.to_ascii_lowercase())
+      }
+      (Some(ch), None) => KeyCode::Char(ch),
+      _ => match key.to_lowercase().as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "esc" => KeyCode::Esc,
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "space" => KeyCode::Char(' '),
+        _ => return Err(eyre!("unknown key {:?}", s)),
+      },
+    };
+    Ok(binding)
+  }
+
+  fn matches(&self, code: KeyCode, mods: KeyModifiers, any_case: bool) -> bool {
+    let same_code = match (self.code, code) {
+      (KeyCode::Char(a), KeyCode::Char(b)) if any_case => {
+        a.eq_ignore_ascii_case(&b)
+      }
+      (a, b) => a == b,
+    };
+    same_code
+      && self.ctrl == mods.contains(KeyModifiers::CONTROL)
+      && self.alt == mods.contains(KeyModifiers::ALT)
+  }
+}
+
+impl fmt::Display for KeyBinding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.ctrl {
+      write!(f, "Ctrl+")?;
+    }
+    if self.alt {
+      write!(f, "Alt+")?;
+    }
+    match self.code {
+      KeyCode::Char(' ') => write!(f, "Space"),
+      KeyCode::Char(ch) if self.ctrl || self.alt => {
+        write!(f, "{}", ch.to_ascii_uppercase())
+      }
+      KeyCode::Char(ch) => write!(f, "{}", ch),
+      KeyCode::Esc => write!(f, "Esc"),
+      other => write!(f, "{:?}", other),
+    }
+  }
+}
+
+/// Which keys do what.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+  bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Keymap {
+  /// Replace all the keys for `action`.
+  pub fn bind(&mut self, action: Action, keys: Vec<KeyBinding>) {
+    self.bindings.retain(|(_, a)| *a != action);
+    self
+      .bindings
+      .extend(keys.into_iter().map(|key| (key, action)));
+  }
+
+  /// What a keypress does. An exact match wins over a movement key pressed
+  /// with shift.
+  pub fn action(&self, code: KeyCode, mods: KeyModifiers) -> Option<Action> {
+    let exact = self
+      .bindings
+      .iter()
+      .find(|(key, _)| key.matches(code, mods, false));
+    let snapped = || {
+      self
+        .bindings
+        .iter()
+        .find(|(key, a)| a.is_movement() && key.matches(code, mods, true))
+    };
+    exact.or_else(snapped).map(|(_, action)| *action)
+  }
+
+  /// A key bound to two different actions, if there is one.
+  pub fn conflict(&self) -> Option<(KeyBinding, Action, Action)> {
+    self.bindings.iter().enumerate().find_map(|(i, &(key, a))| {
+      self.bindings[i + 1..]
+        .iter()
+        .find(|&&(other, b)| other == key && b != a)
+        .map(|&(_, b)| (key, a, b))
+    })
+  }
+
+  /// The first key bound to `action`, for telling the player about it.
+  pub fn describe(&self, action: Action) -> String {
+    match self.bindings.iter().find(|(_, a)| *a == action) {
+      Some((key, _)) => key.to_string(),
+      None => "(unbound)".to_string(),
+    }
+  }
+}
+
+impl Default for Keymap {
+  fn default() -> Self {
+    let bindings = Action::ALL
+      .iter()
+      .flat_map(|&action| {
+        action.default_keys().iter().map(move |key| {
+          (KeyBinding::parse(key).expect("bad default key"), action)
+        })
+      })
+      .collect();
+    Self { bindings }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(s: &str) -> KeyBinding {
+    KeyBinding::parse(s).unwrap()
+  }
+
+  fn char_key(ch: char, ctrl: bool, alt: bool) -> KeyBinding {
+    KeyBinding {
+      code: KeyCode::Char(ch),
+      ctrl,
+      alt,
+    }
+  }
+
+  #[test]
+  fn parses_keys() {
+    assert_eq!(key("q"), char_key('q', false, false));
+    assert_eq!(key("Q"), char_key('Q', false, false));
+    assert_eq!(key("shift+q"), key("Q"));
+    assert_eq!(key("ctrl+z"), char_key('z', true, false));
+    assert_eq!(key("ctrl+Z"), key("ctrl+z"));
+    assert_eq!(key("Ctrl+Alt+x"), char_key('x', true, true));
+    assert_eq!(key("+"), char_key('+', false, false));
+    assert_eq!(key("ctrl++"), char_key('+', true, false));
+    assert_eq!(key("space"), char_key(' ', false, false));
+    assert_eq!(key("Esc").code, KeyCode::Esc);
+    assert_eq!(key("pageup").code, KeyCode::PageUp);
+  }
+
+  #[test]
+  fn rejects_bad_keys() {
+    assert!(KeyBinding::parse("hyper+q").is_err());
+    assert!(KeyBinding::parse("spacebar").is_err());
+    assert!(KeyBinding::parse("").is_err());
+  }
+
+  #[test]
+  fn displays_keys() {
+    assert_eq!(key("ctrl+z").to_string(), "Ctrl+Z");
+    assert_eq!(key("space").to_string(), "Space");
+    assert_eq!(key("esc").to_string(), "Esc");
+    assert_eq!(key("W").to_string(), "W");
+  }
+
+  #[test]
+  fn actions() {
+    let keys = Keymap::default();
+    let none = KeyModifiers::NONE;
+    assert_eq!(keys.action(KeyCode::Char('q'), none), Some(Action::Wall));
+    assert_eq!(keys.action(KeyCode::Char('Q'), none), Some(Action::WallPen));
+    assert_eq!(
+      keys.action(KeyCode::Char('z'), KeyModifiers::CONTROL),
+      Some(Action::Undo)
+    );
+    assert_eq!(keys.action(KeyCode::Char('z'), none), None);
+    assert_eq!(keys.action(KeyCode::Char('x'), none), None);
+  }
+
+  #[test]
+  fn shift_snaps_movement() {
+    let mut keys = Keymap::default();
+    let shift = KeyModifiers::SHIFT;
+    assert_eq!(keys.action(KeyCode::Char('H'), shift), Some(Action::Left));
+    assert_eq!(keys.action(KeyCode::Char('J'), shift), Some(Action::Down));
+    // Only movement ignores case
+    assert_eq!(keys.action(KeyCode::Char('R'), shift), None);
+
+    // and a key bound exactly wins
+    keys.bind(Action::Hint, vec![key("H")]);
+    assert_eq!(keys.action(KeyCode::Char('H'), shift), Some(Action::Hint));
+    assert_eq!(
+      keys.action(KeyCode::Char('h'), KeyModifiers::NONE),
+      Some(Action::Left)
+    );
+  }
+
+  #[test]
+  fn conflicts() {
+    let mut keys = Keymap::default();
+    assert_eq!(keys.conflict(), None);
+    keys.bind(Action::Undo, vec![key("u"), key("u"), key("ctrl+z")]);
+    assert_eq!(keys.conflict(), None);
+    keys.bind(Action::Redo, vec![key("ctrl+Z")]);
+    assert_eq!(
+      keys.conflict(),
+      Some((key("ctrl+z"), Action::Undo, Action::Redo))
+    );
+  }
+}
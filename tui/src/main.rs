@@ -1,19 +1,43 @@
+mod config;
 mod harness;
+mod history;
+mod keys;
+mod pack;
+mod save;
+mod select;
+mod theme;
 
-use std::fs;
+use std::{
+  fs, io,
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH},
+};
 
-use aglet::Direction8;
+use aglet::Coord;
 use argh::FromArgs;
-use eyre::eyre;
+use ariadne::{Color, Label, Report, ReportKind, Source};
+use config::Config;
+use eyre::{eyre, WrapErr};
 use harness::SolveHarness;
-use terminal_tombcrawler::Solution;
+use pack::Pack;
+use save::{format_elapsed, SaveState};
+use select::LevelSelect;
+use terminal_tombcrawler::{
+  generator::{self, GeneratorConfig},
+  solver::Uniqueness,
+  Level, Puzzle, Solution, Tile,
+};
 
 fn main() -> eyre::Result<()> {
   let args: ArgsEntrypoint = argh::from_env();
 
   match args.sub {
-    Subcommands::Play(play) => play.run()?,
+    Subcommands::Play(play) => play.run(&args.config)?,
+    Subcommands::Generate(generate) => generate.run(&args.config)?,
     Subcommands::TestSolver(ts) => ts.run()?,
+    Subcommands::CheckUnique(cu) => cu.run()?,
+    Subcommands::Verify(verify) => verify.run()?,
+    Subcommands::Rate(rate) => rate.run()?,
   }
 
   Ok(())
@@ -22,6 +46,10 @@ fn main() -> eyre::Result<()> {
 #[derive(FromArgs, Debug)]
 /// A terminal clone of Zach Barth's Dungeons and Diagrams.
 struct ArgsEntrypoint {
+  /// settings file to use instead of `terminal-tombcrawler/config.toml` in
+  /// the config directory.
+  #[argh(option)]
+  config: Option<String>,
   #[argh(subcommand)]
   sub: Subcommands,
 }
@@ -30,46 +58,326 @@ struct ArgsEntrypoint {
 #[argh(subcommand)]
 enum Subcommands {
   Play(CmdPlay),
+  Generate(CmdGenerate),
   TestSolver(CmdTestSolver),
+  CheckUnique(CmdCheckUnique),
+  Verify(CmdVerify),
+  Rate(CmdRate),
 }
 
 /// Play a game in the terminal.
 ///
-/// Controls:
-/// - Arrow keys or HJKL to move the cusor. Press shift to snap to the edge of
-///   the grid.
-/// - Q to toggle wall.
-/// - W to toggle known free spaces (as a hint to you).
-/// - Ctrl+C to quit.
-/// - Ctrl+L to redraw the screen.
+/// Give a `.ttc` file to play just that level, or a pack to pick from a
+/// menu. A pack is a directory of `.ttc` files, or a manifest listing them.
+///
+/// Move the cursor around the grid and mark cells as walls, or as known free
+/// spaces to remind yourself. Pens mark every cell the cursor passes over,
+/// and there's undo, redo, a hint for the next cell that can be worked out,
+/// and a way to reveal the solution. Left click toggles a wall and right
+/// click a free space; drag to do the same to every cell along the way.
+///
+/// Which key does what is set in `terminal-tombcrawler/config.toml` in the
+/// config directory (`~/.config` on Linux), with a `[keys]` table of actions:
+/// left, right, up, down, wall, empty, wall_pen, empty_pen, erase_pen,
+/// lift_pen, fill_row, fill_column, hint, undo, redo, roll_back, reveal,
+/// redraw and quit. Colors are picked there too with `theme = "..."`, one of
+/// default, high-contrast, colorblind, monochrome and unicode.
 #[derive(FromArgs, Debug)]
 #[argh(subcommand, name = "play")]
 struct CmdPlay {
-  /// path to `.ttc` file with a puzzle.
+  /// path to a `.ttc` file, a pack directory or a pack manifest.
   #[argh(positional)]
   path: String,
 }
 
 impl CmdPlay {
-  fn run(&self) -> eyre::Result<()> {
-    let file = fs::read_to_string(&self.path)?;
-    let level = terminal_tombcrawler::parse_to_level(&file)
-      .map_err(|e| eyre!("{}", e.to_string()))?;
-    SolveHarness::enter(level)?;
+  fn run(&self, config: &Option<String>) -> eyre::Result<()> {
+    let config = Config::load(config.as_deref())?;
+    let path = Path::new(&self.path);
+    if path.is_dir() || path.extension() != Some("ttc".as_ref()) {
+      LevelSelect::enter(&config, Pack::load(path)?)?;
+      return Ok(());
+    }
+
+    let level = load_level(&self.path)?;
+    play(&config, level)?;
+    Ok(())
+  }
+}
+
+/// Play a level, resuming the saved game if the player wants to.
+fn play(config: &Config, level: Level) -> eyre::Result<SaveState> {
+  confirm_diagnostics(&level)?;
+
+  let save = match SaveState::load(level.puzzle()) {
+    Some(save) if save.is_started() => {
+      let status = if save.solved {
+        "solved"
+      } else {
+        "not solved yet"
+      };
+      eprintln!(
+        "You've played this before ({}, {} played).",
+        status,
+        format_elapsed(save.elapsed)
+      );
+      eprint!("Resume where you left off? [Y/n] ");
+      let mut answer = String::new();
+      io::stdin().read_line(&mut answer)?;
+      if answer.trim().to_lowercase().starts_with('n') {
+        // Start over, but remember it was solved
+        SaveState {
+          solved: save.solved,
+          ..SaveState::new(level.puzzle())
+        }
+      } else {
+        save
+      }
+    }
+    _ => SaveState::new(level.puzzle()),
+  };
+
+  Ok(SolveHarness::enter(config, level, save)?)
+}
+
+/// Print anything `validate` finds and wait for the player to go ahead.
+fn confirm_diagnostics(level: &Level) -> io::Result<()> {
+  let diagnostics = level.validate();
+  if !diagnostics.is_empty() {
+    for diag in diagnostics.iter() {
+      eprintln!("{}", diag);
+    }
+    eprintln!("Press enter to play anyway, or Ctrl+C to quit.");
+    io::stdin().read_line(&mut String::new())?;
+  }
+  Ok(())
+}
+
+/// Generate a fresh puzzle and play it, or save it.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "generate")]
+struct CmdGenerate {
+  /// width of the board.
+  #[argh(option, default = "8")]
+  width: u32,
+  /// height of the board.
+  #[argh(option, default = "8")]
+  height: u32,
+  /// seed for the generator. Picked from the clock if not given.
+  #[argh(option)]
+  seed: Option<u64>,
+  /// number of treasure rooms to place.
+  #[argh(option, default = "1")]
+  treasure_rooms: u32,
+  /// write the puzzle to this `.ttc` file instead of playing it.
+  #[argh(option)]
+  output: Option<String>,
+}
+
+impl CmdGenerate {
+  fn run(&self, config: &Option<String>) -> eyre::Result<()> {
+    let seed = match self.seed {
+      Some(it) => it,
+      None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+    let mut gen_config = GeneratorConfig::new(self.width, self.height, seed);
+    gen_config.treasure_rooms = self.treasure_rooms;
+    let level = generator::generate(&gen_config)
+      .ok_or_else(|| eyre!("couldn't generate a puzzle with those settings"))?;
+    match &self.output {
+      Some(path) => fs::write(path, level.to_ttc_string())?,
+      None => {
+        play(&Config::load(config.as_deref())?, level)?;
+      }
+    }
     Ok(())
   }
 }
 
-/// Temporarily test the solver
+/// Solve a puzzle and print the walls.
 #[derive(FromArgs, Debug)]
 #[argh(subcommand, name = "test-solver")]
-struct CmdTestSolver {}
+struct CmdTestSolver {
+  /// path to `.ttc` file with a puzzle. Uses a built-in test level if not
+  /// given.
+  #[argh(positional)]
+  path: Option<String>,
+}
 
 impl CmdTestSolver {
   fn run(&self) -> eyre::Result<()> {
-    let level = terminal_tombcrawler::parse_to_level(
-      "r#Test level
-      
+    let level = match &self.path {
+      Some(path) => load_level(path)?,
+      None => parse_level("<test level>", TEST_LEVEL)?,
+    };
+    let puzzle = level.puzzle();
+
+    let walls = match puzzle.solve() {
+      Some(it) => it,
+      None => {
+        println!("no solution");
+        return Ok(());
+      }
+    };
+    print_walls(puzzle, &walls);
+    println!("{:?}", puzzle.check_solution(&walls, false));
+
+    Ok(())
+  }
+}
+
+/// Check that each puzzle has exactly one solution.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "check-unique")]
+struct CmdCheckUnique {
+  /// paths to `.ttc` files.
+  #[argh(positional)]
+  paths: Vec<String>,
+}
+
+impl CmdCheckUnique {
+  fn run(&self) -> eyre::Result<()> {
+    let mut bad = 0;
+    for path in self.paths.iter() {
+      let level = load_level(path)?;
+      let puzzle = level.puzzle();
+      match puzzle.uniqueness() {
+        Uniqueness::Unique(_) => println!("{}: unique", path),
+        Uniqueness::NoSolution => {
+          bad += 1;
+          println!("{}: no solution", path);
+        }
+        Uniqueness::Multiple(a, b) => {
+          bad += 1;
+          println!("{}: multiple solutions, for example", path);
+          print_walls(puzzle, &a);
+          println!("and");
+          print_walls(puzzle, &b);
+        }
+      }
+    }
+
+    if bad != 0 {
+      return Err(eyre!("{} puzzle(s) are not uniquely solvable", bad));
+    }
+    Ok(())
+  }
+}
+
+/// Check each puzzle's stored solution against the rules.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "verify")]
+struct CmdVerify {
+  /// paths to `.ttc` files.
+  #[argh(positional)]
+  paths: Vec<String>,
+}
+
+impl CmdVerify {
+  fn run(&self) -> eyre::Result<()> {
+    let mut bad = 0;
+    for path in self.paths.iter() {
+      let level = load_level(path)?;
+      let solution = match level.solution() {
+        Some(it) => it,
+        None => {
+          println!("{}: no stored solution", path);
+          continue;
+        }
+      };
+      match level.puzzle().check_solution(solution, false) {
+        Ok(()) => println!("{}: ok", path),
+        Err(fail) => {
+          bad += 1;
+          println!("{}: {} at {}", path, fail.reason, fail.pos);
+        }
+      }
+    }
+
+    if bad != 0 {
+      return Err(eyre!("{} stored solution(s) are wrong", bad));
+    }
+    Ok(())
+  }
+}
+
+/// Rate how hard each puzzle is, and list them easiest first.
+#[derive(FromArgs, Debug)]
+#[argh(subcommand, name = "rate")]
+struct CmdRate {
+  /// paths to `.ttc` files.
+  #[argh(positional)]
+  paths: Vec<String>,
+}
+
+impl CmdRate {
+  fn run(&self) -> eyre::Result<()> {
+    let mut rated = Vec::new();
+    for path in self.paths.iter() {
+      let level = load_level(path)?;
+      match level.difficulty() {
+        Some(difficulty) => rated.push((path, difficulty)),
+        None => println!("{}: no solution", path),
+      }
+    }
+
+    rated.sort_by_key(|(_, difficulty)| difficulty.score);
+    for (path, difficulty) in rated {
+      println!("{}: {}", path, difficulty);
+    }
+    Ok(())
+  }
+}
+
+/// Read and parse a level, printing a pretty report if it's malformed.
+fn load_level(path: &str) -> eyre::Result<Level> {
+  let file = fs::read_to_string(path)
+    .wrap_err_with(|| format!("couldn't read {}", path))?;
+  parse_level(path, &file)
+}
+
+fn parse_level(name: &str, src: &str) -> eyre::Result<Level> {
+  terminal_tombcrawler::parse_to_level(src).map_err(|e| {
+    // ariadne panics when there's no source to point into, so the eyre error
+    // has to do for those
+    if !src.is_empty() && e.span.end <= src.len() {
+      // ariadne counts in chars, not bytes
+      let start = src[..e.span.start].chars().count();
+      let end = start + src[e.span.clone()].chars().count();
+      let report = Report::build(ReportKind::Error, name, start)
+        .with_message("couldn't parse puzzle")
+        .with_label(
+          Label::new((name, start..end))
+            .with_message(&e.kind)
+            .with_color(Color::Red),
+        )
+        .finish();
+      // If we can't even print the report, the eyre error will do
+      let _ = report.eprint((name, Source::from(src)));
+    }
+    eyre!("{}: {}", name, e.kind)
+  })
+}
+
+fn print_walls(puzzle: &Puzzle, walls: &impl Solution) {
+  for y in 0..puzzle.height() {
+    let row = (0..puzzle.width())
+      .map(|x| {
+        let coord = Coord::new(x, y);
+        match puzzle.get_tile(coord) {
+          Some(Tile::Monster) => '@',
+          Some(Tile::TreasureChest) => '$',
+          None if walls.is_wall(coord) => '#',
+          None => '.',
+        }
+      })
+      .collect::<String>();
+    println!("{}", row);
+  }
+}
+
+const TEST_LEVEL: &str = "Test level
+
 ---
  52125
 5.....
@@ -78,27 +386,4 @@ impl CmdTestSolver {
 2.....
 4.....
 0@...@
-      ",
-    )
-    .map_err(|e| eyre!("{}", e.to_string()))?;
-
-    struct Dummy;
-    impl Solution for Dummy {
-      fn is_wall(&self, coord: aglet::Coord) -> bool {
-        let lookup: [[u8; 5]; 6] = [
-          [1, 1, 1, 1, 1],
-          [1, 0, 0, 0, 1],
-          [1, 0, 0, 0, 1],
-          [1, 0, 0, 0, 1],
-          [1, 1, 0, 1, 1],
-          [0, 0, 0, 0, 0],
-        ];
-        lookup[coord.y as usize][coord.x as usize] != 0
-      }
-    }
-    let solved = level.puzzle().check_solution(&Dummy, true);
-    println!("{:?}", solved);
-
-    Ok(())
-  }
-}
+";
@@ -0,0 +1,189 @@
+//! Puzzle packs: a bunch of levels played in order.
+//!
+//! A pack is either a directory or a manifest file. A directory's levels are
+//! its `.ttc` files sorted by name, unless it has a `pack.txt` manifest in it.
+//!
+//! A manifest's first line is the pack's name. Every line after that is a
+//! path to a `.ttc` file, relative to the manifest, in the order to play
+//! them. Blank lines and lines starting with `#` are skipped.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
+use eyre::eyre;
+use terminal_tombcrawler::Level;
+
+use crate::load_level;
+
+pub const MANIFEST_NAME: &str = "pack.txt";
+
+pub struct Pack {
+  pub name: String,
+  pub levels: Vec<Level>,
+}
+
+impl Pack {
+  /// Load a pack from a directory or a manifest.
+  pub fn load(path: &Path) -> eyre::Result<Pack> {
+    if !path.is_dir() {
+      return Self::load_manifest(path);
+    }
+    let manifest = path.join(MANIFEST_NAME);
+    if manifest.is_file() {
+      return Self::load_manifest(&manifest);
+    }
+
+    let mut paths = fs::read_dir(path)?
+      .map(|entry| entry.map(|e| e.path()))
+      .collect::<Result<Vec<_>, _>>()?;
+    paths.retain(|p| p.extension().is_some_and(|ext| ext == "ttc"));
+    paths.sort();
+
+    let name = path
+      .file_name()
+      .unwrap_or(path.as_os_str())
+      .to_string_lossy()
+      .into_owned();
+    Self::from_paths(name, paths)
+  }
+
+  fn load_manifest(path: &Path) -> eyre::Result<Pack> {
+    let src = fs::read_to_string(path)?;
+    let mut lines = src
+      .lines()
+      .map(str::trim)
+      .filter(|l| !l.is_empty() && !l.starts_with('#'));
+    let name = lines
+      .next()
+      .ok_or_else(|| eyre!("{}: the manifest is empty", path.display()))?
+      .to_string();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let paths = lines.map(|l| dir.join(l)).collect();
+    Self::from_paths(name, paths)
+  }
+
+  fn from_paths(name: String, paths: Vec<PathBuf>) -> eyre::Result<Pack> {
+    if paths.is_empty() {
+      return Err(eyre!("the pack {:?} has no levels in it", name));
+    }
+    let levels = paths
+      .iter()
+      .map(|path| load_level(&path.to_string_lossy()))
+      .collect::<eyre::Result<_>>()?;
+    Ok(Pack { name, levels })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::env;
+
+  use super::*;
+
+  /// A fresh directory to build packs in.
+  fn scratch(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!(
+      "tombcrawler-pack-{}-{}",
+      name,
+      std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn write_level(path: &Path, title: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, format!("{}\n---\n 0\n0.\n", title)).unwrap();
+  }
+
+  fn titles(pack: &Pack) -> Vec<&str> {
+    pack.levels.iter().map(|level| level.title()).collect()
+  }
+
+  #[test]
+  fn manifest_skips_comments_and_blanks() {
+    let dir = scratch("comments");
+    write_level(&dir.join("a.ttc"), "A");
+    write_level(&dir.join("b.ttc"), "B");
+    let manifest = dir.join("list.txt");
+    let lines = [
+      "# made by hand",
+      "",
+      "  Hand Picked  ",
+      "b.ttc",
+      "",
+      "# a.ttc is last",
+      "  a.ttc",
+    ];
+    fs::write(&manifest, lines.join("\n")).unwrap();
+
+    let pack = Pack::load(&manifest).unwrap();
+    assert_eq!(pack.name, "Hand Picked");
+    assert_eq!(titles(&pack), ["B", "A"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn manifest_paths_are_relative_to_it() {
+    let dir = scratch("relative");
+    write_level(&dir.join("levels").join("first.ttc"), "First");
+    write_level(&dir.join("pack").join("second.ttc"), "Second");
+    fs::write(
+      dir.join("pack").join(MANIFEST_NAME),
+      "Elsewhere\n../levels/first.ttc\nsecond.ttc\n",
+    )
+    .unwrap();
+
+    // Found both by name and by the directory it's in
+    for path in [dir.join("pack").join(MANIFEST_NAME), dir.join("pack")] {
+      let pack = Pack::load(&path).unwrap();
+      assert_eq!(pack.name, "Elsewhere");
+      assert_eq!(titles(&pack), ["First", "Second"]);
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn directories_without_manifests_sort_by_name() {
+    let dir = scratch("sorted");
+    write_level(&dir.join("2-two.ttc"), "Two");
+    write_level(&dir.join("1-one.ttc"), "One");
+    fs::write(dir.join("notes.md"), "not a level").unwrap();
+
+    let pack = Pack::load(&dir).unwrap();
+    assert_eq!(titles(&pack), ["One", "Two"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn empty_manifest() {
+    let dir = scratch("empty");
+    let manifest = dir.join("list.txt");
+    fs::write(&manifest, "# nothing here\n\n").unwrap();
+
+    let err = Pack::load(&manifest).err().unwrap();
+    assert!(
+      err.to_string().ends_with("the manifest is empty"),
+      "{}",
+      err
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn no_levels() {
+    let dir = scratch("no-levels");
+    let manifest = dir.join("list.txt");
+    fs::write(&manifest, "Just A Name\n# and a comment\n").unwrap();
+
+    let err = Pack::load(&manifest).err().unwrap();
+    assert_eq!(
+      err.to_string(),
+      "the pack \"Just A Name\" has no levels in it"
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
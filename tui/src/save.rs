@@ -0,0 +1,245 @@
+//! Saving games in progress, so they can be picked back up later.
+//!
+//! Each puzzle gets its own file in the user's data directory, named after a
+//! hash of its hints and tiles. Moving or renaming the `.ttc` keeps the save;
+//! changing the puzzle itself doesn't.
+
+use std::{fmt, fs, io, path::PathBuf, time::Duration};
+
+use aglet::Coord;
+use terminal_tombcrawler::{
+  board::{Board, Cell},
+  Puzzle, Tile,
+};
+
+pub struct SaveState {
+  pub board: Board,
+  /// Whether the player ever solved it (without revealing the answer).
+  pub solved: bool,
+  /// Whether the answer was revealed onto the board, so it can't count as
+  /// solving it.
+  pub revealed: bool,
+  /// Time spent playing, up to solving it if they did.
+  pub elapsed: Duration,
+}
+
+impl SaveState {
+  /// A save with nothing done yet.
+  pub fn new(puzzle: &Puzzle) -> Self {
+    Self {
+      board: Board::new(puzzle.clone()),
+      solved: false,
+      revealed: false,
+      elapsed: Duration::ZERO,
+    }
+  }
+
+  /// Load the save for this puzzle, if there is one and it makes sense.
+  pub fn load(puzzle: &Puzzle) -> Option<Self> {
+    let src = fs::read_to_string(save_path(puzzle)?).ok()?;
+    Self::parse(puzzle, &src)
+  }
+
+  /// Write the save out. Does nothing if there's nowhere to put it.
+  pub fn store(&self) -> io::Result<()> {
+    let path = match save_path(self.board.puzzle()) {
+      Some(it) => it,
+      None => return Ok(()),
+    };
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir)?;
+    }
+    fs::write(path, self.to_string())
+  }
+
+  /// Whether there's anything here worth resuming.
+  pub fn is_started(&self) -> bool {
+    self.solved
+      || self
+        .board
+        .coords()
+        .any(|coord| self.board.get(coord) != Cell::Unknown)
+  }
+
+  fn parse(puzzle: &Puzzle, src: &str) -> Option<Self> {
+    let mut lines = src.lines().peekable();
+    let solved = yes_or_no(lines.next()?.strip_prefix("solved ")?)?;
+    let secs = lines.next()?.strip_prefix("elapsed ")?.parse().ok()?;
+    // Older saves don't say
+    let revealed = match lines.peek().and_then(|l| l.strip_prefix("revealed "))
+    {
+      Some(answer) => {
+        lines.next();
+        yes_or_no(answer)?
+      }
+      None => false,
+    };
+
+    let mut board = Board::new(puzzle.clone());
+    let mut rows = 0;
+    for (y, line) in lines.enumerate() {
+      if line.chars().count() != puzzle.width() as usize {
+        return None;
+      }
+      for (x, ch) in line.chars().enumerate() {
+        let cell = match ch {
+          '#' => Cell::Wall,
+          '*' => Cell::Open,
+          '.' => Cell::Unknown,
+          _ => return None,
+        };
+        board.set(Coord::new(x as u32, y as u32), cell);
+      }
+      rows += 1;
+    }
+    if rows != puzzle.height() {
+      return None;
+    }
+
+    Some(Self {
+      board,
+      solved,
+      revealed,
+      elapsed: Duration::from_secs(secs),
+    })
+  }
+}
+
+impl fmt::Display for SaveState {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "solved {}", if self.solved { "yes" } else { "no" })?;
+    writeln!(f, "elapsed {}", self.elapsed.as_secs())?;
+    writeln!(f, "revealed {}", if self.revealed { "yes" } else { "no" })?;
+    for y in 0..self.board.height() {
+      for x in 0..self.board.width() {
+        let ch = match self.board.get(Coord::new(x, y)) {
+          Cell::Wall => '#',
+          Cell::Open => '*',
+          Cell::Unknown => '.',
+        };
+        write!(f, "{}", ch)?;
+      }
+      writeln!(f)?;
+    }
+    Ok(())
+  }
+}
+
+fn yes_or_no(s: &str) -> Option<bool> {
+  match s {
+    "yes" => Some(true),
+    "no" => Some(false),
+    _ => None,
+  }
+}
+
+/// Like `3:07`, or `1:03:07` past an hour.
+pub fn format_elapsed(elapsed: Duration) -> String {
+  let secs = elapsed.as_secs();
+  let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+  if hours > 0 {
+    format!("{}:{:02}:{:02}", hours, mins, secs)
+  } else {
+    format!("{}:{:02}", mins, secs)
+  }
+}
+
+fn save_path(puzzle: &Puzzle) -> Option<PathBuf> {
+  let dir = dirs::data_local_dir()?;
+  Some(
+    dir
+      .join("terminal-tombcrawler")
+      .join("saves")
+      .join(format!("{:016x}.sav", puzzle_hash(puzzle))),
+  )
+}
+
+/// FNV-1a over the hints and tiles. This has to stay the same between
+/// versions, so no `DefaultHasher`.
+fn puzzle_hash(puzzle: &Puzzle) -> u64 {
+  let mut bytes = Vec::new();
+  bytes.extend(puzzle.width().to_le_bytes());
+  bytes.extend(puzzle.height().to_le_bytes());
+  bytes.extend(puzzle.top_hints());
+  bytes.extend(puzzle.side_hints());
+  for y in 0..puzzle.height() {
+    for x in 0..puzzle.width() {
+      bytes.push(match puzzle.get_tile(Coord::new(x, y)) {
+        None => 0,
+        Some(Tile::Monster) => 1,
+        Some(Tile::TreasureChest) => 2,
+      });
+    }
+  }
+
+  bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+    (hash ^ b as u64).wrapping_mul(0x100000001b3)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use terminal_tombcrawler::parse_to_level;
+
+  use super::*;
+
+  fn puzzle() -> Puzzle {
+    parse_to_level("Save Test\n---\n 102\n1@..\n2..$\n")
+      .unwrap()
+      .puzzle()
+      .clone()
+  }
+
+  #[test]
+  fn round_trips() {
+    let puzzle = puzzle();
+    let mut save = SaveState::new(&puzzle);
+    save.board.set(Coord::new(0, 1), Cell::Wall);
+    save.board.set(Coord::new(2, 0), Cell::Open);
+    save.revealed = true;
+    save.elapsed = Duration::from_secs(187);
+
+    let written = save.to_string();
+    assert_eq!(written, "solved no\nelapsed 187\nrevealed yes\n..*\n#..\n");
+    let again = SaveState::parse(&puzzle, &written).unwrap();
+    assert_eq!(again.to_string(), written);
+    assert_eq!(again.board.get(Coord::new(0, 1)), Cell::Wall);
+    assert!(again.revealed && !again.solved);
+  }
+
+  #[test]
+  fn reads_saves_from_before_reveals_were_kept() {
+    let save =
+      SaveState::parse(&puzzle(), "solved yes\nelapsed 5\n...\n...\n").unwrap();
+    assert!(save.solved && !save.revealed);
+    assert_eq!(save.elapsed, Duration::from_secs(5));
+  }
+
+  #[test]
+  fn rejects_saves_that_dont_fit() {
+    let puzzle = puzzle();
+    let header = "solved no\nelapsed 0\nrevealed no\n";
+    for rows in ["...\n", "...\n...\n...\n", "...\n....\n", "...\n.x.\n"] {
+      let src = format!("{}{}", header, rows);
+      assert!(SaveState::parse(&puzzle, &src).is_none(), "{:?}", rows);
+    }
+    let bad_flag = "solved maybe\nelapsed 0\n...\n...\n";
+    assert!(SaveState::parse(&puzzle, bad_flag).is_none());
+  }
+
+  #[test]
+  fn hash_is_stable() {
+    // Saves are found by this, so changing it loses everyone's progress
+    let brightleaf = include_str!("../../puzzles/01-brightleaf.ttc");
+    let level = parse_to_level(brightleaf).unwrap();
+    assert_eq!(puzzle_hash(level.puzzle()), 0xd46bcdf2ae2d55d2);
+    assert_ne!(puzzle_hash(level.puzzle()), puzzle_hash(&puzzle()));
+  }
+
+  #[test]
+  fn formats_elapsed() {
+    assert_eq!(format_elapsed(Duration::from_secs(0)), "0:00");
+    assert_eq!(format_elapsed(Duration::from_secs(187)), "3:07");
+    assert_eq!(format_elapsed(Duration::from_secs(3787)), "1:03:07");
+  }
+}
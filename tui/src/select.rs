@@ -0,0 +1,198 @@
+//! Level select menu
+
+use std::io::{self, Stdout, Write};
+
+use crossterm::{
+  cursor::MoveTo,
+  event::{self, DisableMouseCapture, Event, KeyCode, KeyEventKind},
+  style::{Attribute, Print, ResetColor, SetAttribute},
+  terminal::{
+    self, disable_raw_mode, enable_raw_mode, Clear, ClearType,
+    EnterAlternateScreen, LeaveAlternateScreen,
+  },
+  QueueableCommand,
+};
+
+use crate::{
+  config::Config,
+  keys::Action,
+  pack::Pack,
+  play,
+  save::{format_elapsed, SaveState},
+  theme,
+};
+
+const START_X: u16 = 2;
+const START_Y: u16 = 2;
+
+/// Where the first level is listed.
+const LIST_Y: u16 = 4;
+
+pub struct LevelSelect<'c> {
+  config: &'c Config,
+  pack: Pack,
+  cursor: usize,
+  /// The first level on screen, when there are too many to show at once.
+  scroll: usize,
+  /// Saved progress on each level.
+  saves: Vec<Option<SaveState>>,
+}
+
+impl<'c> LevelSelect<'c> {
+  /// Show the menu until the player quits.
+  pub fn enter(config: &'c Config, pack: Pack) -> eyre::Result<()> {
+    let saves = pack
+      .levels
+      .iter()
+      .map(|level| SaveState::load(level.puzzle()))
+      .collect();
+    let mut select = Self {
+      config,
+      pack,
+      cursor: 0,
+      scroll: 0,
+      saves,
+    };
+
+    let mut stdout = io::stdout();
+    open_screen(&mut stdout)?;
+    let _restore = RestoreOnDrop;
+    loop {
+      select.draw(&mut stdout)?;
+
+      let ev = match event::read()? {
+        Event::Key(ev)
+          if matches!(ev.kind, KeyEventKind::Press | KeyEventKind::Repeat) =>
+        {
+          ev
+        }
+        _ => continue,
+      };
+      let count = select.pack.levels.len();
+      match (config.keys.action(ev.code, ev.modifiers), ev.code) {
+        (Some(Action::Quit), _) => break,
+        (Some(Action::Up), _) => {
+          select.cursor = (select.cursor + count - 1) % count;
+        }
+        (Some(Action::Down), _) => {
+          select.cursor = (select.cursor + 1) % count;
+        }
+        (_, KeyCode::Home) => select.cursor = 0,
+        (_, KeyCode::End) => select.cursor = count - 1,
+        (_, KeyCode::Enter) => {
+          // The harness sets up the terminal itself
+          close_screen(&mut stdout)?;
+          let level = select.pack.levels[select.cursor].clone();
+          select.saves[select.cursor] = Some(play(config, level)?);
+          open_screen(&mut stdout)?;
+        }
+        _ => {}
+      }
+    }
+
+    Ok(())
+  }
+
+  fn draw(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+    let theme = &self.config.theme;
+    stdout
+      .queue(MoveTo(START_X, START_Y))?
+      .queue(ResetColor)?
+      .queue(SetAttribute(Attribute::Reset))?
+      .queue(Print(&self.pack.name))?
+      .queue(Clear(ClearType::UntilNewLine))?;
+
+    let title_width = self
+      .pack
+      .levels
+      .iter()
+      .map(|level| level.title().chars().count())
+      .max()
+      .unwrap_or(0);
+
+    // Show as many as fit, moving the window along to keep the cursor in it
+    let (_, term_height) = terminal::size()?;
+    let rows = term_height.saturating_sub(LIST_Y).max(1) as usize;
+    if self.cursor < self.scroll {
+      self.scroll = self.cursor;
+    } else if self.cursor >= self.scroll + rows {
+      self.scroll = self.cursor + 1 - rows;
+    }
+
+    let shown = self.pack.levels.iter().enumerate().skip(self.scroll);
+    for (i, level) in shown.take(rows) {
+      let puzzle = level.puzzle();
+      let (marker, style) = match &self.saves[i] {
+        Some(save) if save.solved => (
+          format!("solved in {}", format_elapsed(save.elapsed)),
+          Some(&theme.good),
+        ),
+        Some(save) if save.is_started() => (
+          format!("started, {}", format_elapsed(save.elapsed)),
+          Some(&theme.started),
+        ),
+        _ => (String::new(), None),
+      };
+      let line = format!(
+        "{} {:<tw$}  {:>2}x{:<2}  {}",
+        if i == self.cursor { '>' } else { ' ' },
+        level.title(),
+        puzzle.width(),
+        puzzle.height(),
+        marker,
+        tw = title_width,
+      );
+      stdout.queue(MoveTo(START_X, LIST_Y + (i - self.scroll) as u16))?;
+      match style {
+        Some(style) => theme::set_text(stdout, style)?,
+        None => {
+          stdout
+            .queue(ResetColor)?
+            .queue(SetAttribute(Attribute::Reset))?;
+        }
+      }
+      stdout
+        .queue(Print(line))?
+        .queue(Clear(ClearType::UntilNewLine))?;
+    }
+
+    // Whatever a bigger window had below the list
+    stdout
+      .queue(ResetColor)?
+      .queue(SetAttribute(Attribute::Reset))?
+      .queue(Clear(ClearType::FromCursorDown))?;
+
+    stdout
+      .queue(MoveTo(START_X, LIST_Y + (self.cursor - self.scroll) as u16))?
+      .flush()?;
+    Ok(())
+  }
+}
+
+fn open_screen(stdout: &mut Stdout) -> io::Result<()> {
+  enable_raw_mode()?;
+  stdout
+    .queue(EnterAlternateScreen)?
+    .queue(Clear(ClearType::All))?
+    .flush()
+}
+
+/// Also undoes anything a level left behind if it bailed out partway.
+fn close_screen(stdout: &mut Stdout) -> io::Result<()> {
+  stdout
+    .queue(DisableMouseCapture)?
+    .queue(LeaveAlternateScreen)?
+    .flush()?;
+  disable_raw_mode()
+}
+
+/// Puts the terminal back when the menu is done with it, however it ends, so
+/// an error doesn't leave the player stuck in raw mode.
+struct RestoreOnDrop;
+
+impl Drop for RestoreOnDrop {
+  fn drop(&mut self) {
+    // Nowhere left to report this
+    let _ = close_screen(&mut io::stdout());
+  }
+}
@@ -0,0 +1,252 @@
+//! How the board looks.
+
+use std::io::{self, Stdout};
+
+use crossterm::{
+  style::{
+    Attribute, Attributes, Color, Colors, SetAttribute, SetAttributes,
+    SetColors, SetForegroundColor,
+  },
+  QueueableCommand,
+};
+use terminal_tombcrawler::{board::Cell, Tile};
+
+/// A character on the board and how to draw it.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+  pub ch: char,
+  pub colors: Colors,
+  pub attrs: Attributes,
+}
+
+/// How to draw some text.
+#[derive(Debug, Clone, Copy)]
+pub struct Text {
+  pub fg: Color,
+  pub attrs: Attributes,
+}
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+  pub wall: Glyph,
+  pub empty: Glyph,
+  pub unknown: Glyph,
+  pub monster: Glyph,
+  pub chest: Glyph,
+  /// A hint whose line has all its walls.
+  pub hint_met: Text,
+  /// A hint whose line has too many walls.
+  pub hint_over: Text,
+  /// A hint whose line still needs walls.
+  pub hint_under: Text,
+  /// Things that are nice to know, like the difficulty.
+  pub faint: Text,
+  /// Solving things.
+  pub good: Text,
+  /// Levels that are started but not solved.
+  pub started: Text,
+  /// Draw walls as box-drawing lines joined to their neighbors, instead of
+  /// `wall.ch`.
+  pub box_walls: bool,
+}
+
+impl Theme {
+  pub const NAMES: [&'static str; 5] = [
+    "default",
+    "high-contrast",
+    "colorblind",
+    "monochrome",
+    "unicode",
+  ];
+
+  pub fn by_name(name: &str) -> Option<Theme> {
+    match name {
+      "default" => Some(Theme::default()),
+      "high-contrast" => Some(Theme::high_contrast()),
+      "colorblind" => Some(Theme::colorblind()),
+      "monochrome" => Some(Theme::monochrome()),
+      "unicode" => Some(Theme::unicode()),
+      _ => None,
+    }
+  }
+
+  /// Bright colors on black, and nothing dim.
+  fn high_contrast() -> Theme {
+    let bold = Attributes::from(Attribute::Bold);
+    Theme {
+      wall: glyph('#', Color::Black, Color::White, bold),
+      empty: glyph('*', Color::Cyan, Color::Black, bold),
+      unknown: glyph('.', Color::White, Color::Black, Attributes::default()),
+      monster: glyph('@', Color::Red, Color::Black, bold),
+      chest: glyph('$', Color::Yellow, Color::Black, bold),
+      hint_met: text(Color::Green, bold),
+      hint_over: text(Color::Red, bold | Attribute::Reverse),
+      hint_under: text(Color::White, bold),
+      faint: text(Color::White, Attributes::default()),
+      good: text(Color::Green, bold),
+      started: text(Color::Yellow, bold),
+      box_walls: false,
+    }
+  }
+
+  /// Blue and orange-ish instead of green and red, and too many walls is
+  /// shown by more than color.
+  fn colorblind() -> Theme {
+    Theme {
+      hint_met: text(Color::Blue, Attribute::Bold.into()),
+      hint_over: text(Color::DarkYellow, Attribute::Reverse.into()),
+      good: text(Color::Blue, Attribute::Bold.into()),
+      started: text(Color::DarkYellow, Attributes::default()),
+      empty: glyph('*', Color::Cyan, Color::Reset, Attribute::Italic.into()),
+      ..Theme::default()
+    }
+  }
+
+  /// No colors at all, just attributes.
+  fn monochrome() -> Theme {
+    let plain = Attributes::default();
+    let mono = |ch, attrs| glyph(ch, Color::Reset, Color::Reset, attrs);
+    Theme {
+      wall: mono('#', Attribute::Reverse.into()),
+      empty: mono('*', plain),
+      unknown: mono('.', Attribute::Dim.into()),
+      monster: mono('@', Attribute::Bold.into()),
+      chest: mono('$', Attribute::Bold.into()),
+      hint_met: text(Color::Reset, Attribute::Dim.into()),
+      hint_over: text(
+        Color::Reset,
+        Attributes::from(Attribute::Reverse) | Attribute::Bold,
+      ),
+      hint_under: text(Color::Reset, plain),
+      faint: text(Color::Reset, Attribute::Dim.into()),
+      good: text(Color::Reset, Attribute::Bold.into()),
+      started: text(Color::Reset, plain),
+      box_walls: false,
+    }
+  }
+
+  /// Box-drawing walls and nicer symbols, with the default colors.
+  fn unicode() -> Theme {
+    let default = Theme::default();
+    Theme {
+      wall: Glyph {
+        colors: Colors::new(Color::White, Color::Reset),
+        ..default.wall
+      },
+      empty: Glyph {
+        ch: '○',
+        ..default.empty
+      },
+      unknown: Glyph {
+        ch: '·',
+        ..default.unknown
+      },
+      monster: Glyph {
+        ch: 'Ω',
+        ..default.monster
+      },
+      chest: Glyph {
+        ch: '¤',
+        ..default.chest
+      },
+      box_walls: true,
+      ..default
+    }
+  }
+
+  pub fn tile(&self, tile: Tile) -> Glyph {
+    match tile {
+      Tile::Monster => self.monster,
+      Tile::TreasureChest => self.chest,
+    }
+  }
+
+  pub fn cell(&self, cell: Cell) -> Glyph {
+    match cell {
+      Cell::Wall => self.wall,
+      Cell::Open => self.empty,
+      Cell::Unknown => self.unknown,
+    }
+  }
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Theme {
+      wall: glyph('#', Color::White, Color::DarkGrey, Attribute::Bold.into()),
+      empty: glyph(
+        '*',
+        Color::DarkMagenta,
+        Color::Reset,
+        Attribute::Italic.into(),
+      ),
+      unknown: glyph('.', Color::DarkGrey, Color::Reset, Attributes::default()),
+      monster: glyph('@', Color::Red, Color::Reset, Attribute::Bold.into()),
+      chest: glyph('$', Color::Yellow, Color::Reset, Attribute::Bold.into()),
+      hint_met: text(Color::DarkGreen, Attributes::default()),
+      hint_over: text(Color::Red, Attributes::default()),
+      hint_under: text(Color::White, Attributes::default()),
+      faint: text(Color::DarkGrey, Attributes::default()),
+      good: text(Color::Green, Attributes::default()),
+      started: text(Color::Yellow, Attributes::default()),
+      box_walls: false,
+    }
+  }
+}
+
+/// The box-drawing character for a wall, given which of its neighbors are
+/// walls.
+pub fn box_wall(north: bool, east: bool, south: bool, west: bool) -> char {
+  match (north, east, south, west) {
+    (false, false, false, false) => '■',
+    (true, false, false, false) => '╵',
+    (false, true, false, false) => '╶',
+    (false, false, true, false) => '╷',
+    (false, false, false, true) => '╴',
+    (true, true, false, false) => '└',
+    (true, false, true, false) => '│',
+    (true, false, false, true) => '┘',
+    (false, true, true, false) => '┌',
+    (false, true, false, true) => '─',
+    (false, false, true, true) => '┐',
+    (true, true, true, false) => '├',
+    (true, true, false, true) => '┴',
+    (true, false, true, true) => '┤',
+    (false, true, true, true) => '┬',
+    (true, true, true, true) => '┼',
+  }
+}
+
+/// Switch to drawing a glyph, forgetting whatever was drawn before.
+pub fn set_glyph(
+  stdout: &mut Stdout,
+  glyph: &Glyph,
+  extra: Attributes,
+) -> io::Result<()> {
+  stdout
+    .queue(SetAttribute(Attribute::Reset))?
+    .queue(SetColors(glyph.colors))?
+    .queue(SetAttributes(glyph.attrs | extra))?;
+  Ok(())
+}
+
+/// Switch to drawing some text, forgetting whatever was drawn before.
+pub fn set_text(stdout: &mut Stdout, text: &Text) -> io::Result<()> {
+  stdout
+    .queue(SetAttribute(Attribute::Reset))?
+    .queue(SetForegroundColor(text.fg))?
+    .queue(SetAttributes(text.attrs))?;
+  Ok(())
+}
+
+fn glyph(ch: char, fg: Color, bg: Color, attrs: Attributes) -> Glyph {
+  Glyph {
+    ch,
+    colors: Colors::new(fg, bg),
+    attrs,
+  }
+}
+
+fn text(fg: Color, attrs: Attributes) -> Text {
+  Text { fg, attrs }
+}